@@ -8,11 +8,13 @@ fn main() {
 
 	argyle::FlagsBuilder::new("Flags")
 		.private()
-		.with_flag("Disable", Some("# Disable Shitlist."))
+		.with_flag("Force", Some("# Overwrite (--init)."))
+		.with_flag("Help", Some("# Print Help."))
+		.with_flag("NoEscalate", Some("# Don't Escalate Privileges."))
 		.with_flag("Quiet", None)
-		.with_flag("Show", Some("# Show Only."))
 		.with_flag("Stdout", Some("# Print to STDOUT."))
 		.with_complex_flag("Systemd", ["Yes"], Some("# Systemd Use.\n\nImplies `--yes`."))
+		.with_flag("Update", Some("# Update After Edit."))
 		.with_flag("Yes", Some("# Assume Yes (Don't Prompt)."))
 		.save(out_path("flags.rs"));
 }
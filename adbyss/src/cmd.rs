@@ -0,0 +1,252 @@
+/*!
+# Adbyss: Commands
+*/
+
+use crate::{
+	AdbyssError,
+	err,
+	ListEdit,
+};
+use adbyss_psl::Domain;
+
+
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+/// # Command.
+///
+/// This is the one thing Adbyss has been asked to do, whether by subcommand
+/// (`adbyss show`), or one of the older flags (`adbyss --show`).
+pub(super) enum Command {
+	/// # Check a Host.
+	Check(Option<Domain>),
+
+	/// # Check the Config.
+	CheckConfig,
+
+	/// # Preview Changes.
+	Diff,
+
+	/// # Remove Adbyss Entries.
+	Disable,
+
+	/// # Edit the Include/Exclude Lists.
+	Edit(ListEdit, Vec<Domain>),
+
+	/// # Write a Fresh Config.
+	Init(Option<String>),
+
+	/// # Pause (Seconds).
+	Pause(u32),
+
+	/// # Print the Config.
+	PrintConfig,
+
+	/// # Restore the Backup.
+	Restore,
+
+	/// # Resume (Unpause).
+	Resume,
+
+	/// # Print the Shitlist.
+	Show,
+
+	/// # Print the Status.
+	Status,
+
+	/// # Update the Hostfile.
+	Update,
+}
+
+impl Command {
+	/// # From Subcommand Name.
+	pub(super) fn from_name(src: &str) -> Option<Self> {
+		match src {
+			"check" => Some(Self::Check(None)),
+			"diff" => Some(Self::Diff),
+			"disable" => Some(Self::Disable),
+			"restore" => Some(Self::Restore),
+			"show" => Some(Self::Show),
+			"status" => Some(Self::Status),
+			"update" => Some(Self::Update),
+			_ => ListEdit::from_name(src).map(|e| Self::Edit(e, Vec::new())),
+		}
+	}
+
+	/// # Name.
+	///
+	/// Return the subcommand or flag name, for error messages.
+	pub(super) const fn name(&self) -> &'static str {
+		match self {
+			Self::Check(_) => "check",
+			Self::CheckConfig => "--check-config",
+			Self::Diff => "diff",
+			Self::Disable => "disable",
+			Self::Edit(e, _) => e.as_str(),
+			Self::Init(_) => "--init",
+			Self::Pause(_) => "--pause",
+			Self::PrintConfig => "--print-config",
+			Self::Restore => "restore",
+			Self::Resume => "--resume",
+			Self::Show => "show",
+			Self::Status => "status",
+			Self::Update => "update",
+		}
+	}
+
+	/// # Help.
+	///
+	/// Return the command-specific help screen, if any.
+	pub(super) const fn help(&self) -> Option<&'static str> {
+		match self {
+			Self::Check(_) => Some(err::HELP_CHECK),
+			Self::Diff => Some(err::HELP_DIFF),
+			Self::Disable => Some(err::HELP_DISABLE),
+			Self::Edit(..) => Some(err::HELP_EDIT),
+			Self::Restore => Some(err::HELP_RESTORE),
+			Self::Show => Some(err::HELP_SHOW),
+			Self::Status => Some(err::HELP_STATUS),
+			Self::Update => Some(err::HELP_UPDATE),
+			Self::CheckConfig | Self::Init(_) | Self::Pause(_) | Self::PrintConfig |
+			Self::Resume => None,
+		}
+	}
+
+	/// # Reportable?
+	///
+	/// Returns `true` for commands that (re)build the hostfile, i.e. `update`
+	/// and — if followed by `--update` — the list edits.
+	pub(super) const fn reportable(&self, update: bool) -> bool {
+		match self {
+			Self::Update => true,
+			Self::Edit(..) => update,
+			_ => false,
+		}
+	}
+
+	/// # Same Kind?
+	///
+	/// Returns `true` if the commands are the same, ignoring their arguments.
+	fn same_kind(&self, other: &Self) -> bool {
+		match (self, other) {
+			(Self::Edit(a, _), Self::Edit(b, _)) => a == b,
+			_ => std::mem::discriminant(self) == std::mem::discriminant(other),
+		}
+	}
+}
+
+impl Command {
+	/// # Set Command.
+	///
+	/// Set the command if there isn't one yet. Repeating the same command —
+	/// e.g. `show --show` — is fine, but anything else is a conflict.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if a different command was already set.
+	pub(super) fn set(dst: &mut Option<Self>, cmd: Self) -> Result<(), AdbyssError> {
+		match dst {
+			None => { dst.replace(cmd); },
+			Some(old) if old.same_kind(&cmd) => {
+				// Later values win.
+				if matches!(cmd, Self::Check(Some(_)) | Self::Init(Some(_)) | Self::Pause(_)) {
+					*old = cmd;
+				}
+			},
+			Some(old) => return Err(AdbyssError::Conflict(format!("{}, {}", old.name(), cmd.name()))),
+		}
+		Ok(())
+	}
+
+	/// # Push Argument.
+	///
+	/// Pass a positional argument to the command, returning it back if the
+	/// command doesn't take any (more).
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the argument is meant to be a host but
+	/// isn't one.
+	pub(super) fn push_arg(&mut self, arg: String) -> Result<Option<String>, AdbyssError> {
+		match self {
+			Self::Check(dom @ None) => {
+				let d = Domain::new(&arg).ok_or(AdbyssError::InvalidCli(arg))?;
+				dom.replace(d);
+			},
+			Self::Edit(_, hosts) => {
+				let d = Domain::new(&arg).ok_or(AdbyssError::InvalidCli(arg))?;
+				hosts.push(d);
+			},
+			Self::Init(dst @ None) => { dst.replace(arg); },
+			_ => return Ok(Some(arg)),
+		}
+		Ok(None)
+	}
+
+	/// # Finish.
+	///
+	/// Make sure the command has everything it needs.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if a required host is missing.
+	pub(super) fn finish(&self) -> Result<(), AdbyssError> {
+		match self {
+			Self::Check(None) => Err(AdbyssError::InvalidCli("check <host>".to_owned())),
+			Self::Edit(e, hosts) if hosts.is_empty() =>
+				Err(AdbyssError::InvalidCli(format!("{} <host>", e.as_str()))),
+			_ => Ok(()),
+		}
+	}
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_set() {
+		let mut cmd = None;
+		assert!(Command::set(&mut cmd, Command::Show).is_ok());
+		assert!(Command::set(&mut cmd, Command::Show).is_ok());
+		assert!(matches!(
+			Command::set(&mut cmd, Command::Disable),
+			Err(AdbyssError::Conflict(_)),
+		));
+		assert_eq!(cmd, Some(Command::Show));
+
+		// Different edits conflict too.
+		let mut cmd = Command::from_name("allow");
+		assert!(Command::set(&mut cmd, Command::Edit(ListEdit::Allow, Vec::new())).is_ok());
+		assert!(Command::set(&mut cmd, Command::Edit(ListEdit::Block, Vec::new())).is_err());
+	}
+
+	#[test]
+	fn t_args() {
+		let mut cmd = Command::from_name("check").expect("Missing check.");
+		assert!(cmd.finish().is_err());
+		assert!(cmd.push_arg("bad host".to_owned()).is_err());
+		assert!(matches!(cmd.push_arg("Ads.Example.com".to_owned()), Ok(None)));
+		assert!(cmd.finish().is_ok());
+		assert_eq!(
+			cmd,
+			Command::Check(Some(Domain::new("ads.example.com").expect("Invalid domain."))),
+		);
+
+		// Only one host can be checked.
+		assert!(matches!(cmd.push_arg("other.example.com".to_owned()), Ok(Some(_))));
+
+		// But many can be edited.
+		let mut cmd = Command::from_name("block").expect("Missing block.");
+		assert!(cmd.finish().is_err());
+		for host in ["a.example.com", "b.example.com"] {
+			assert!(matches!(cmd.push_arg(host.to_owned()), Ok(None)));
+		}
+		assert!(cmd.finish().is_ok());
+
+		// Others don't take arguments.
+		let mut cmd = Command::Show;
+		assert!(matches!(cmd.push_arg("foo".to_owned()), Ok(Some(_))));
+	}
+}
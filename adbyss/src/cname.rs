@@ -0,0 +1,311 @@
+/*!
+# Adbyss: CNAME-Cloaking Lists
+*/
+
+use adbyss_psl::Domain;
+use crate::AdbyssError;
+use serde::{
+	de,
+	Deserialize,
+};
+use std::{
+	borrow::Cow,
+	cell::RefCell,
+	collections::{
+		btree_map,
+		BTreeMap,
+	},
+	fmt,
+	path::PathBuf,
+	str::Lines,
+};
+
+
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+/// # CNAME List Source.
+///
+/// Trackers increasingly hide behind first-party hostnames that are really
+/// just CNAMEs pointing back to them. Hostfiles can't follow CNAMEs, but they
+/// can block the first-party names, so long as somebody has found them.
+///
+/// These lists — local files or remote URLs — can be any of:
+///
+/// * `AdGuard`'s JSON map of disguised hosts to their tracker targets;
+/// * `AdGuard`-style `||first.party.host^` rules, which lack targets;
+/// * `first.party.host tracker.target` pairs, one per line, separated by
+///   whitespace or a comma.
+///
+/// For the line-based formats, lines starting with `#` or `!` are comments.
+pub(super) enum CnameSource {
+	/// # Local File.
+	File(PathBuf),
+
+	/// # Remote URL.
+	Url(String),
+}
+
+impl<'de> Deserialize<'de> for CnameSource {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where D: de::Deserializer<'de> {
+		let raw = Cow::<str>::deserialize(deserializer)?;
+		let raw = raw.trim();
+		if raw.starts_with("https://") || raw.starts_with("http://") {
+			Ok(Self::Url(raw.to_owned()))
+		}
+		else if raw.is_empty() { Err(de::Error::custom("empty cname source")) }
+		else { Ok(Self::File(PathBuf::from(raw))) }
+	}
+}
+
+impl fmt::Display for CnameSource {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::File(p) => write!(f, "{}", p.display()),
+			Self::Url(u) => f.write_str(u),
+		}
+	}
+}
+
+impl CnameSource {
+	/// # Remote?
+	pub(super) const fn is_remote(&self) -> bool { matches!(self, Self::Url(_)) }
+
+	/// # Fetch Raw Data.
+	///
+	/// ## Errors
+	///
+	/// This returns an error if the file cannot be read or downloaded.
+	pub(super) fn fetch_raw(&self) -> Result<String, AdbyssError> {
+		match self {
+			Self::File(p) => std::fs::read_to_string(p)
+				.map_err(|e| AdbyssError::Read(p.to_string_lossy().into_owned(), e.to_string())),
+			Self::Url(u) => crate::source::download(u)
+				.map_err(|e| AdbyssError::SourceFetch(u.clone(), e)),
+		}
+	}
+}
+
+
+
+/// # CNAME Pairs Iter.
+///
+/// Tease the `(host, target)` pairs out of a raw CNAME list. The target is
+/// `None` for `||host^` rules, which don't say.
+///
+/// Lines that aren't pairs or rules, or whose target isn't a valid host, are
+/// skipped, as are JSON documents that aren't a map of strings.
+pub(super) enum CnamePairs<'a> {
+	/// # Line-Based (Pairs or Rules).
+	Lines(Lines<'a>),
+
+	/// # JSON Map.
+	Map(btree_map::IntoIter<&'a str, &'a str>),
+}
+
+impl<'a> CnamePairs<'a> {
+	/// # New.
+	pub(super) fn new(raw: &'a str) -> Self {
+		if raw.trim_start().starts_with('{') {
+			Self::Map(
+				serde_json::from_str::<BTreeMap<&str, &str>>(raw)
+					.unwrap_or_default()
+					.into_iter()
+			)
+		}
+		else { Self::Lines(raw.lines()) }
+	}
+}
+
+impl<'a> Iterator for CnamePairs<'a> {
+	type Item = (&'a str, Option<&'a str>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		match self {
+			Self::Lines(lines) => loop {
+				let line = lines.next()?.trim();
+				if line.is_empty() || line.starts_with(['#', '!']) { continue; }
+
+				// An AdGuard-style rule, maybe with modifiers.
+				if let Some(rule) = line.strip_prefix("||") {
+					let rule = rule.split_once('$').map_or(rule, |(r, _)| r);
+					if let Some(host) = rule.strip_suffix('^') && ! host.is_empty() {
+						return Some((host, None));
+					}
+					continue;
+				}
+
+				let mut parts = line.split(|c: char| c == ',' || c.is_whitespace())
+					.filter(|p| ! p.is_empty());
+				if
+					let Some(host) = parts.next() &&
+					let Some(target) = parts.next() &&
+					parts.next().is_none_or(|p| p.starts_with('#')) &&
+					Domain::new(target).is_some()
+				{
+					return Some((host, Some(target)));
+				}
+			},
+			Self::Map(map) => loop {
+				let (host, target) = map.next()?;
+				if Domain::new(target).is_some() { return Some((host, Some(target))); }
+			},
+		}
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let hint = match self {
+			Self::Lines(lines) => lines.size_hint().1,
+			Self::Map(map) => map.size_hint().1,
+		};
+		(0, hint)
+	}
+}
+
+impl std::iter::FusedIterator for CnamePairs<'_> {}
+
+
+
+#[derive(Clone, Default)]
+/// # CNAME Targets.
+///
+/// This holds the tracker targets of the CNAME-sourced hosts from the last
+/// shitlist build, so they can be shown alongside them.
+pub(super) struct CnameTargets(RefCell<BTreeMap<String, String>>);
+
+impl fmt::Debug for CnameTargets {
+	/// # Debug.
+	///
+	/// The contents are run-specific, so are omitted.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("CnameTargets")
+	}
+}
+
+impl CnameTargets {
+	/// # Clear.
+	pub(super) fn clear(&self) { self.0.borrow_mut().clear(); }
+
+	/// # Get Target.
+	pub(super) fn get(&self, host: &str) -> Option<String> {
+		self.0.borrow().get(host).cloned()
+	}
+
+	/// # Insert.
+	///
+	/// Record the target for the host, normalizing both. (The first target
+	/// found for a given host wins.)
+	pub(super) fn insert(&self, host: &str, target: &str) {
+		if
+			let Some(host) = Domain::new(host) &&
+			let Some(target) = Domain::new(target)
+		{
+			self.0.borrow_mut().entry(host.take()).or_insert_with(|| target.take());
+		}
+	}
+
+	/// # Alias.
+	///
+	/// Give `alias` — e.g. a www counterpart — the same target as `host`, if
+	/// it has one.
+	pub(super) fn alias(&self, host: &str, alias: &str) {
+		let mut map = self.0.borrow_mut();
+		if let Some(target) = map.get(host).cloned() {
+			map.entry(alias.to_owned()).or_insert(target);
+		}
+	}
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_pairs() {
+		let raw = std::fs::read_to_string("skel/cname.txt")
+			.expect("Unable to read CNAME fixture.");
+		assert_eq!(
+			CnamePairs::new(&raw).collect::<Vec<_>>(),
+			[
+				("metrics.blobfolio.com", Some("blobfolio.com.ssl.sc.omtrdc.net")),
+				("smetrics.example.co.uk", Some("example.co.uk.ssl.d1.sc.omtrdc.net")),
+				("a8.example.com", Some("example.eulerian.net")),
+				("bad_host.example.org", Some("example.tracker.example.com")),
+			],
+		);
+
+		// AdGuard's JSON map of disguised trackers.
+		let raw = std::fs::read_to_string("skel/cname.json")
+			.expect("Unable to read CNAME fixture.");
+		assert_eq!(
+			CnamePairs::new(&raw).collect::<Vec<_>>(),
+			[
+				("a8.example.com", Some("example.eulerian.net")),
+				("metrics.blobfolio.com", Some("blobfolio.com.ssl.sc.omtrdc.net")),
+				("smetrics.example.co.uk", Some("example.co.uk.ssl.d1.sc.omtrdc.net")),
+			],
+		);
+
+		// And its rules.
+		let raw = std::fs::read_to_string("skel/cname-rules.txt")
+			.expect("Unable to read CNAME fixture.");
+		assert_eq!(
+			CnamePairs::new(&raw).collect::<Vec<_>>(),
+			[
+				("a8.example.com", None),
+				("metrics.blobfolio.com", None),
+				("smetrics.example.co.uk", None),
+			],
+		);
+
+		// Broken JSON should yield nothing.
+		assert_eq!(CnamePairs::new(r#"{"a8.example.com": 5}"#).count(), 0);
+	}
+
+	#[test]
+	fn t_targets() {
+		let targets = CnameTargets::default();
+		targets.insert("A8.Example.com", "Example.Eulerian.net");
+		targets.insert("a8.example.com", "other.eulerian.net");
+		targets.insert("bad_host.example.org", "example.eulerian.net");
+		assert_eq!(targets.get("a8.example.com").as_deref(), Some("example.eulerian.net"));
+		assert!(targets.get("bad_host.example.org").is_none());
+
+		targets.alias("a8.example.com", "www.a8.example.com");
+		assert_eq!(targets.get("www.a8.example.com").as_deref(), Some("example.eulerian.net"));
+
+		targets.clear();
+		assert!(targets.get("a8.example.com").is_none());
+	}
+
+	#[test]
+	fn t_source() {
+		#[derive(Deserialize)]
+		struct Wrapper { cname_sources: Vec<CnameSource> }
+
+		let src: Wrapper = toml::from_str(r#"cname_sources = [
+			"skel/cname.txt",
+			" https://example.com/cname.txt ",
+		]"#).expect("Unable to parse CNAME sources.");
+
+		assert_eq!(
+			src.cname_sources,
+			[
+				CnameSource::File(PathBuf::from("skel/cname.txt")),
+				CnameSource::Url("https://example.com/cname.txt".to_owned()),
+			],
+		);
+		assert!(! src.cname_sources[0].is_remote());
+		assert!(src.cname_sources[1].is_remote());
+		assert!(src.cname_sources[0].fetch_raw().is_ok());
+
+		// Missing files are no good.
+		let missing = CnameSource::File(PathBuf::from("skel/missing.txt"));
+		assert!(matches!(missing.fetch_raw(), Err(AdbyssError::Read(..))));
+
+		// Nor are empties.
+		assert!(toml::from_str::<Wrapper>(r#"cname_sources = [ "" ]"#).is_err());
+	}
+}
@@ -0,0 +1,774 @@
+/*!
+# Adbyss: Config Files
+*/
+
+use crate::{
+	AdbyssError,
+	Settings,
+	source::{
+		Category,
+		Source,
+	},
+};
+use serde::Deserialize;
+use std::{
+	collections::BTreeMap,
+	fmt,
+	io::ErrorKind,
+	ops::Range,
+	path::{
+		Path,
+		PathBuf,
+	},
+};
+use toml::{
+	de::{
+		DeTable,
+		DeValue,
+		ValueDeserializer,
+	},
+	Spanned,
+	Table,
+	Value,
+};
+
+
+
+/// # Default Settings.
+///
+/// This mirrors `Settings::default`, but in TOML form so it can be merged —
+/// and printed — like any other config file.
+const DEFAULTS: &str = r#"
+hostfile = "/etc/hosts"
+backup = true
+compact = false
+source_adaway = true
+source_adbyss = true
+source_stevenblack = true
+source_yoyo = true
+cname_sources = []
+www_expansion = "strip"
+exclude = []
+regexclude = []
+regexinclude = []
+include = []
+protect_defaults = true
+protect = []
+protect_strict = false
+block_suffixes = []
+allow_suffixes = []
+post_write = []
+
+[categories]
+ads = true
+malware = true
+fakenews = false
+gambling = false
+porn = false
+social = false
+"#;
+
+/// # Default Origin.
+const ORIGIN_DEFAULT: &str = "default";
+
+/// # Command Line Origin.
+const ORIGIN_CLI: &str = "command line";
+
+/// # Profile Key.
+const PROFILE_KEY: &str = "profile";
+
+/// # Profile Environment Variable.
+pub(super) const PROFILE_ENV: &str = "ADBYSS_PROFILE";
+
+
+
+#[derive(Debug, Clone)]
+/// # Merged Config.
+///
+/// This holds the raw TOML for the main config file merged with any drop-ins
+/// from the sibling `.d` directory (e.g. `/etc/adbyss.d/*.toml`), along with
+/// the file(s) each value came from.
+///
+/// Drop-ins are merged in lexical order. Lists are appended to, tables are
+/// merged key by key, and everything else is simply overridden.
+///
+/// Any `[profile.<name>]` sections are merged the same way, but held apart
+/// until the end, when the selected one (if any) is layered on top.
+pub(super) struct Config {
+	/// # Merged Table.
+	table: Table,
+
+	/// # Merged Profiles.
+	profiles: Table,
+
+	/// # Active Profile.
+	profile: Option<String>,
+
+	/// # Files Loaded.
+	files: Vec<String>,
+
+	/// # Origins (By Key Path).
+	origins: BTreeMap<String, Vec<String>>,
+}
+
+impl fmt::Display for Config {
+	/// # Display.
+	///
+	/// Print the effective config as TOML, with each value preceded by a
+	/// comment naming the file(s) it came from.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if let Some(profile) = &self.profile {
+			writeln!(f, "# Profile: {profile}\n")?;
+		}
+
+		// Plain values first.
+		for (k, v) in &self.table {
+			if ! v.is_table() { self.fmt_value(f, k, k, v)?; }
+		}
+
+		// Then the tables.
+		for (k, v) in &self.table {
+			if let Value::Table(t) = v {
+				write!(f, "\n[{k}]\n")?;
+				for (k2, v2) in t {
+					self.fmt_value(f, &format!("{k}.{k2}"), k2, v2)?;
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl Config {
+	/// # Load.
+	///
+	/// Load and merge the default settings, the main config file (if any),
+	/// and the drop-ins sitting alongside it, then layer the chosen profile
+	/// (if any) on top.
+	///
+	/// If no file is given, only the drop-ins for the default location are
+	/// considered.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if any of the files cannot be read or
+	/// parsed, or contain invalid settings, or if the profile doesn't exist.
+	pub(super) fn load(src: Option<&Path>, profile: Option<&str>)
+	-> Result<Self, AdbyssError> {
+		let mut out = Self {
+			table: Table::new(),
+			profiles: Table::new(),
+			profile: None,
+			files: Vec::new(),
+			origins: BTreeMap::new(),
+		};
+		let defaults = defaults()?;
+		merge_table(&mut out.table, defaults, "", &mut out.origins, &|_| ORIGIN_DEFAULT.to_owned());
+
+		let main = src.unwrap_or_else(|| Path::new(Settings::DEFAULT_CONFIG));
+		if src.is_some() { out.merge_file(main)?; }
+		for file in dropins(&main.with_extension("d"))? { out.merge_file(&file)?; }
+
+		if let Some(profile) = profile { out.apply_profile(profile)?; }
+
+		Ok(out)
+	}
+
+	/// # Files Loaded.
+	pub(super) fn files(&self) -> &[String] { &self.files }
+
+	/// # Table.
+	pub(super) const fn table(&self) -> &Table { &self.table }
+
+	/// # Merge File.
+	///
+	/// Parse and validate a config file, then merge it in.
+	fn merge_file(&mut self, src: &Path) -> Result<(), AdbyssError> {
+		let name = src.to_string_lossy();
+		let raw = std::fs::read_to_string(src)
+			.map_err(|e| AdbyssError::Read(name.to_string(), e.to_string()))?;
+		// Make sure the file — and its profiles — make sense on their own so
+		// any errors can be pinned to it.
+		validate(&raw).map_err(|e| AdbyssError::Parse(format!("{name}: {e}")))?;
+
+		let mut table = raw.parse::<Table>()
+			.map_err(|e| AdbyssError::Parse(format!("{name}: {}", e.message())))?;
+		let profiles = match table.remove(PROFILE_KEY) {
+			Some(Value::Table(t)) => t,
+			_ => Table::new(),
+		};
+
+		let origin = |_: &str| name.to_string();
+		merge_table(&mut self.table, table, "", &mut self.origins, &origin);
+		merge_table(&mut self.profiles, profiles, "profile.", &mut self.origins, &origin);
+		self.files.push(name.into_owned());
+		Ok(())
+	}
+
+	/// # Apply Profile.
+	///
+	/// Layer the named profile on top of the base settings, using the same
+	/// rules as everything else.
+	fn apply_profile(&mut self, name: &str) -> Result<(), AdbyssError> {
+		let Some(Value::Table(src)) = self.profiles.get(name).cloned() else {
+			return Err(AdbyssError::Profile(name.to_owned()));
+		};
+
+		// The origins for the profile's values have already been recorded
+		// (by file); we just need to tweak the labels.
+		let prefix = format!("{PROFILE_KEY}.{name}.");
+		let labels: BTreeMap<String, String> = self.origins.iter()
+			.filter_map(|(k, v)| k.strip_prefix(&prefix).map(|k| (
+				k.to_owned(),
+				format!("{} [{PROFILE_KEY}.{name}]", v.join(", ")),
+			)))
+			.collect();
+
+		merge_table(
+			&mut self.table,
+			src,
+			"",
+			&mut self.origins,
+			&|path| labels.get(path).cloned().unwrap_or_default(),
+		);
+		self.profile = Some(name.to_owned());
+		Ok(())
+	}
+
+	/// # Apply Overrides.
+	///
+	/// Layer the command line overrides on top of everything else.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the overrides don't make sense.
+	pub(super) fn apply_overrides(&mut self, overrides: &Overrides) -> Result<(), AdbyssError> {
+		if overrides.table.is_empty() { return Ok(()); }
+
+		Value::Table(overrides.table.clone()).try_into::<Settings>()
+			.map_err(|e| AdbyssError::InvalidCli(e.message().trim().to_owned()))?;
+
+		merge_table(
+			&mut self.table,
+			overrides.table.clone(),
+			"",
+			&mut self.origins,
+			&|_| ORIGIN_CLI.to_owned(),
+		);
+		Ok(())
+	}
+
+	/// # Format Value.
+	///
+	/// Write a single `key = value` line (or lines, for non-empty lists),
+	/// preceded by its origin(s).
+	fn fmt_value(&self, f: &mut fmt::Formatter<'_>, path: &str, key: &str, v: &Value)
+	-> fmt::Result {
+		if let Some(origins) = self.origins.get(path) {
+			writeln!(f, "# {}", origins.join(", "))?;
+		}
+
+		match v {
+			Value::Array(arr) if ! arr.is_empty() => {
+				writeln!(f, "{key} = [")?;
+				for v2 in arr { writeln!(f, "\t{v2},")?; }
+				writeln!(f, "]")
+			},
+			_ => writeln!(f, "{key} = {v}"),
+		}
+	}
+}
+
+
+
+#[derive(Debug, Clone, Default)]
+/// # Command Line Overrides.
+///
+/// This holds any settings passed via the command line — `--hostfile`,
+/// `--exclude`, etc. — in TOML form, along with the original arguments (so
+/// they can be passed along to future runs).
+pub(super) struct Overrides {
+	/// # Settings.
+	table: Table,
+
+	/// # Original Arguments.
+	args: Vec<String>,
+}
+
+impl Overrides {
+	/// # Original Arguments.
+	pub(super) fn args(&self) -> &[String] { &self.args }
+
+	/// # Set.
+	///
+	/// Set (replace) the value for a key.
+	pub(super) fn set<V: Into<Value>>(&mut self, arg: &str, key: &str, value: V) {
+		let value = value.into();
+		self.args.push(arg.to_owned());
+		if let Value::String(s) = &value { self.args.push(s.clone()); }
+		self.table.insert(key.to_owned(), value);
+	}
+
+	/// # Push.
+	///
+	/// Append a value to a list.
+	pub(super) fn push(&mut self, arg: &str, key: &str, value: String) {
+		self.args.push(arg.to_owned());
+		self.args.push(value.clone());
+		if let Some(Value::Array(arr)) = self.table.get_mut(key) { arr.push(Value::String(value)); }
+		else { self.table.insert(key.to_owned(), Value::Array(vec![Value::String(value)])); }
+	}
+
+	/// # Toggle Sources.
+	///
+	/// Enable or disable sources from a list like `+adaway,-yoyo`.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if any of the sources are unknown.
+	pub(super) fn toggle_sources(&mut self, src: &str) -> Result<(), AdbyssError> {
+		let list: Vec<(String, bool)> = toggles(src).collect();
+		if let Some((name, _)) = list.iter().find(|(n, _)| ! Source::ALL.iter().any(|s| s.key() == n)) {
+			return Err(AdbyssError::InvalidCli(format!("--source {name}")));
+		}
+
+		for (name, enabled) in list {
+			self.table.insert(format!("source_{name}"), Value::Boolean(enabled));
+		}
+		self.args.push("--source".to_owned());
+		self.args.push(src.to_owned());
+		Ok(())
+	}
+
+	/// # Toggle Categories.
+	///
+	/// Enable or disable categories from a list like `+social,-ads`.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if any of the categories are unknown.
+	pub(super) fn toggle_categories(&mut self, src: &str) -> Result<(), AdbyssError> {
+		let list: Vec<(String, bool)> = toggles(src).collect();
+		if let Some((name, _)) = list.iter().find(|(n, _)| ! Category::ALL.iter().any(|c| c.as_str() == n)) {
+			return Err(AdbyssError::InvalidCli(format!("--category {name}")));
+		}
+
+		let mut categories = match self.table.remove("categories") {
+			Some(Value::Table(t)) => t,
+			_ => Table::new(),
+		};
+		for (name, enabled) in list {
+			categories.insert(name, Value::Boolean(enabled));
+		}
+		self.table.insert("categories".to_owned(), Value::Table(categories));
+		self.args.push("--category".to_owned());
+		self.args.push(src.to_owned());
+		Ok(())
+	}
+}
+
+
+
+/// # Defaults.
+///
+/// Return the default settings as a TOML table.
+pub(super) fn defaults() -> Result<Table, AdbyssError> {
+	DEFAULTS.parse::<Table>().map_err(|e| AdbyssError::Parse(e.to_string()))
+}
+
+/// # Drop-Ins.
+///
+/// Return the `*.toml` files in the directory, sorted lexically. A missing
+/// directory is fine; it just means there aren't any.
+pub(super) fn dropins(dir: &Path) -> Result<Vec<PathBuf>, AdbyssError> {
+	let iter = match std::fs::read_dir(dir) {
+		Ok(iter) => iter,
+		Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(e) => return Err(AdbyssError::Read(dir.to_string_lossy().into_owned(), e.to_string())),
+	};
+
+	let mut out: Vec<PathBuf> = iter
+		.filter_map(|e| e.ok().map(|e| e.path()))
+		.filter(|p|
+			p.extension().is_some_and(|e| e == "toml") &&
+			p.file_name().is_some_and(|n| ! n.as_encoded_bytes().starts_with(b".")) &&
+			p.is_file()
+		)
+		.collect();
+	out.sort_unstable();
+	Ok(out)
+}
+
+/// # Toggles.
+///
+/// Parse a comma- and/or space-separated list of names, each optionally
+/// prefixed with `+` (enable, the default) or `-` (disable).
+fn toggles(src: &str) -> impl Iterator<Item=(String, bool)> {
+	src.split(|c: char| c == ',' || c.is_whitespace())
+		.filter(|v| ! v.is_empty())
+		.map(|v| {
+			let enabled = ! v.starts_with('-');
+			(v.trim_start_matches(['+', '-']).to_ascii_lowercase(), enabled)
+		})
+}
+
+/// # Merge Table.
+///
+/// Recursively merge `src` into `dst`, recording the origin of each value
+/// touched. (The origin callback receives the key path relative to `src`.)
+fn merge_table<F: Fn(&str) -> String>(
+	dst: &mut Table,
+	src: Table,
+	prefix: &str,
+	origins: &mut BTreeMap<String, Vec<String>>,
+	origin: &F,
+) {
+	merge_table_inner(dst, src, prefix, "", origins, origin);
+}
+
+/// # Merge Table (Recursive).
+fn merge_table_inner<F: Fn(&str) -> String>(
+	dst: &mut Table,
+	src: Table,
+	prefix: &str,
+	rel: &str,
+	origins: &mut BTreeMap<String, Vec<String>>,
+	origin: &F,
+) {
+	for (k, v) in src {
+		let path = format!("{prefix}{k}");
+		let rel = format!("{rel}{k}");
+		match (dst.get_mut(&k), v) {
+			// Tables merge key by key.
+			(Some(Value::Table(a)), Value::Table(b)) => {
+				merge_table_inner(a, b, &format!("{path}."), &format!("{rel}."), origins, origin);
+			},
+			(_, Value::Table(b)) => {
+				origins.remove(&path);
+				let mut a = Table::new();
+				merge_table_inner(&mut a, b, &format!("{path}."), &format!("{rel}."), origins, origin);
+				dst.insert(k, Value::Table(a));
+			},
+			// Lists append.
+			(Some(Value::Array(a)), Value::Array(b)) => {
+				a.extend(b);
+				let entry = origins.entry(path).or_default();
+				if entry.iter().all(|o| o == ORIGIN_DEFAULT) { entry.truncate(0); }
+				entry.push(origin(&rel));
+			},
+			// Everything else is replaced.
+			(_, v) => {
+				dst.insert(k, v);
+				origins.insert(path, vec![origin(&rel)]);
+			},
+		}
+	}
+}
+
+/// # Validate.
+///
+/// Make sure a config file — and each of its profiles — deserializes cleanly
+/// on its own, returning a message with the line and column of the first
+/// problem if not.
+fn validate(raw: &str) -> Result<(), String> {
+	let mut root = DeTable::parse(raw).map_err(|e| fmt_error(raw, &e))?;
+	let span = root.span();
+	let profiles = root.get_mut().remove(PROFILE_KEY);
+	validate_table(raw, Spanned::new(span, DeValue::Table(root.into_inner())))?;
+
+	let Some(profiles) = profiles else { return Ok(()); };
+	let span = profiles.span();
+	let DeValue::Table(profiles) = profiles.into_inner() else {
+		return Err(fmt_span(raw, span, &format!("{PROFILE_KEY} must be a table")));
+	};
+	for (k, v) in profiles {
+		if ! v.get_ref().is_table() {
+			return Err(fmt_span(raw, v.span(), &format!("{PROFILE_KEY}.{} must be a table", k.get_ref())));
+		}
+		validate_table(raw, v)?;
+	}
+
+	Ok(())
+}
+
+/// # Validate Table.
+fn validate_table(raw: &str, table: Spanned<DeValue<'_>>) -> Result<(), String> {
+	Settings::deserialize(ValueDeserializer::from(table))
+		.map(|_| ())
+		.map_err(|e| fmt_error(raw, &e))
+}
+
+/// # Format Error.
+fn fmt_error(raw: &str, err: &toml::de::Error) -> String {
+	err.span().map_or_else(
+		|| err.message().to_owned(),
+		|span| fmt_span(raw, span, err.message()),
+	)
+}
+
+/// # Format Error (With Position).
+///
+/// Prefix the message with the (one-based) line and column corresponding to
+/// the start of the span.
+fn fmt_span(raw: &str, span: Range<usize>, msg: &str) -> String {
+	let before = raw.get(..span.start).unwrap_or(raw);
+	let line = before.matches('\n').count() + 1;
+	let col = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+	format!("line {line}, column {col}: {}", msg.trim())
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_defaults() {
+		// The TOML defaults should match the struct ones.
+		let settings = toml::from_str::<Settings>(DEFAULTS)
+			.expect("Unable to parse defaults.");
+		assert_eq!(
+			format!("{settings:?}"),
+			format!("{:?}", Settings::default()),
+		);
+	}
+
+	#[test]
+	fn t_dropins() {
+		let conf = Config::load(Some(Path::new("skel/dropin.toml")), None)
+			.expect("Unable to load config.");
+		let table = conf.table();
+
+		// Scalars are overridden by the last file to set them.
+		assert_eq!(table.get("backup"), Some(&Value::Boolean(true)));
+		assert_eq!(table.get("compact"), Some(&Value::Boolean(true)));
+		assert_eq!(table.get("source_yoyo"), Some(&Value::Boolean(false)));
+
+		// Lists are appended.
+		assert_eq!(
+			table.get("exclude").and_then(Value::as_array).map(Vec::as_slice),
+			Some([
+				Value::from("ads.example.com"),
+				Value::from("*.tracker.example.com"),
+				Value::from("metrics.example.org"),
+			].as_slice()),
+		);
+
+		// Tables are merged.
+		let categories = table.get("categories").and_then(Value::as_table)
+			.expect("Missing categories.");
+		assert_eq!(categories.get("ads"), Some(&Value::Boolean(true)));
+		assert_eq!(categories.get("gambling"), Some(&Value::Boolean(true)));
+
+		// Origins.
+		assert_eq!(conf.origins.get("hostfile").map(Vec::as_slice), Some(["default".to_owned()].as_slice()));
+		assert_eq!(
+			conf.origins.get("backup").map(Vec::as_slice),
+			Some(["skel/dropin.d/20-second.toml".to_owned()].as_slice()),
+		);
+		assert_eq!(
+			conf.origins.get("exclude").map(Vec::as_slice),
+			Some([
+				"skel/dropin.toml".to_owned(),
+				"skel/dropin.d/10-first.toml".to_owned(),
+			].as_slice()),
+		);
+		assert_eq!(
+			conf.origins.get("categories.gambling").map(Vec::as_slice),
+			Some(["skel/dropin.d/10-first.toml".to_owned()].as_slice()),
+		);
+
+		// The effective settings should reflect all of that.
+		let settings = Settings::from_config(&conf).expect("Unable to parse config.");
+		assert!(settings.backup());
+		assert!(settings.compact());
+
+		assert_eq!(
+			conf.files(),
+			["skel/dropin.toml", "skel/dropin.d/10-first.toml", "skel/dropin.d/20-second.toml"],
+		);
+
+		// Printing should work too.
+		let printed = conf.to_string();
+		assert!(printed.contains("# skel/dropin.toml, skel/dropin.d/10-first.toml\nexclude = [\n"));
+		assert!(printed.contains("\n[categories]\n"));
+		assert!(printed.parse::<Table>().is_ok(), "Printed config is not valid TOML.");
+	}
+
+	#[test]
+	fn t_profile() {
+		let conf = Config::load(Some(Path::new("skel/dropin.toml")), Some("kiosk"))
+			.expect("Unable to load config.");
+		let table = conf.table();
+
+		// The profile's scalars win, even over later drop-ins.
+		assert_eq!(table.get("compact"), Some(&Value::Boolean(false)));
+		assert_eq!(table.get("source_yoyo"), Some(&Value::Boolean(true)));
+		assert_eq!(table.get("backup"), Some(&Value::Boolean(true)));
+
+		// Its lists are appended.
+		assert_eq!(
+			table.get("exclude").and_then(Value::as_array).map(Vec::len),
+			Some(4),
+		);
+
+		// Its tables are merged.
+		let categories = table.get("categories").and_then(Value::as_table)
+			.expect("Missing categories.");
+		assert_eq!(categories.get("gambling"), Some(&Value::Boolean(true)));
+		assert_eq!(categories.get("social"), Some(&Value::Boolean(true)));
+
+		// The profiles themselves shouldn't leak through.
+		assert!(! table.contains_key(PROFILE_KEY));
+
+		// Origins.
+		assert_eq!(
+			conf.origins.get("source_yoyo").map(Vec::as_slice),
+			Some(["skel/dropin.d/20-second.toml [profile.kiosk]".to_owned()].as_slice()),
+		);
+		assert_eq!(
+			conf.origins.get("categories.social").map(Vec::as_slice),
+			Some(["skel/dropin.toml [profile.kiosk]".to_owned()].as_slice()),
+		);
+		assert!(conf.to_string().starts_with("# Profile: kiosk\n"));
+
+		// The effective settings should reflect all of that.
+		let settings = Settings::from_config(&conf).expect("Unable to parse config.");
+		assert!(! settings.compact());
+
+		// Unknown profiles are no good.
+		assert!(matches!(
+			Config::load(Some(Path::new("skel/dropin.toml")), Some("office")),
+			Err(AdbyssError::Profile(_)),
+		));
+	}
+
+	#[test]
+	fn t_overrides() {
+		let mut overrides = Overrides::default();
+		overrides.set("--no-backup", "backup", false);
+		overrides.set("--hostfile", "hostfile", "/tmp/hosts");
+		overrides.push("--exclude", "exclude", "cli.example.com".to_owned());
+		overrides.toggle_sources("+adaway, -YOYO").expect("Unable to toggle sources.");
+		overrides.toggle_categories("social").expect("Unable to toggle categories.");
+		overrides.toggle_categories("-ads").expect("Unable to toggle categories.");
+
+		// Unknown names are no good.
+		assert!(overrides.toggle_sources("+oisd").is_err());
+		assert!(overrides.toggle_categories("crypto").is_err());
+
+		assert_eq!(
+			overrides.args(),
+			[
+				"--no-backup",
+				"--hostfile", "/tmp/hosts",
+				"--exclude", "cli.example.com",
+				"--source", "+adaway, -YOYO",
+				"--category", "social",
+				"--category", "-ads",
+			],
+		);
+
+		let mut conf = Config::load(Some(Path::new("skel/dropin.toml")), None)
+			.expect("Unable to load config.");
+		conf.apply_overrides(&overrides).expect("Unable to apply overrides.");
+		let table = conf.table();
+
+		assert_eq!(table.get("backup"), Some(&Value::Boolean(false)));
+		assert_eq!(table.get("hostfile"), Some(&Value::from("/tmp/hosts")));
+		assert_eq!(table.get("source_adaway"), Some(&Value::Boolean(true)));
+		assert_eq!(table.get("source_yoyo"), Some(&Value::Boolean(false)));
+		assert_eq!(
+			table.get("exclude").and_then(Value::as_array).and_then(|a| a.last()),
+			Some(&Value::from("cli.example.com")),
+		);
+		let categories = table.get("categories").and_then(Value::as_table)
+			.expect("Missing categories.");
+		assert_eq!(categories.get("ads"), Some(&Value::Boolean(false)));
+		assert_eq!(categories.get("gambling"), Some(&Value::Boolean(true)));
+		assert_eq!(categories.get("social"), Some(&Value::Boolean(true)));
+
+		assert_eq!(
+			conf.origins.get("exclude").map(Vec::as_slice),
+			Some([
+				"skel/dropin.toml".to_owned(),
+				"skel/dropin.d/10-first.toml".to_owned(),
+				"command line".to_owned(),
+			].as_slice()),
+		);
+
+		// Bad values are no good either.
+		let mut overrides = Overrides::default();
+		overrides.push("--include", "include", "batman".to_owned());
+		assert!(matches!(
+			conf.apply_overrides(&overrides),
+			Err(AdbyssError::InvalidCli(_)),
+		));
+	}
+
+	#[test]
+	fn t_invalid() {
+		// Errors should name the offending file.
+		let dir = std::env::temp_dir().join(format!("adbyss-t-invalid-{}.d", std::process::id()));
+		let main = dir.with_extension("toml");
+		let written = std::fs::create_dir_all(&dir)
+			.and_then(|()| std::fs::write(dir.join("10-bad.toml"), "compact = \"maybe\"\n"))
+			.and_then(|()| std::fs::write(dir.join("README"), "Not TOML.\n"))
+			.and_then(|()| std::fs::write(&main, "backup = false\n"));
+		let res = Config::load(Some(&main), None);
+
+		let _res = std::fs::remove_dir_all(&dir);
+		let _res = std::fs::remove_file(&main);
+
+		written.expect("Unable to write test configs.");
+		match res {
+			Err(AdbyssError::Parse(e)) => assert!(
+				e.contains("10-bad.toml: line 1, column 11:"),
+				"{e}",
+			),
+			_ => panic!("Expected a parse error."),
+		}
+	}
+
+	#[test]
+	fn t_validate() {
+		assert!(validate("").is_ok());
+		assert!(validate("backup = false\n\n[profile.kiosk]\ncompact = true\n").is_ok());
+
+		for (raw, expected) in [
+			// Typos.
+			(
+				"backup = false\nsource_stevnblack = false\n",
+				"line 2, column 1: unknown field `source_stevnblack`",
+			),
+			(
+				"[categories]\nads = true\ngamble = true\n",
+				"line 3, column 1: unknown field `gamble`",
+			),
+			// Wrong types.
+			(
+				"include = \"batman.com\"\n",
+				"line 1, column 11: invalid type: string \"batman.com\", expected a sequence",
+			),
+			// Bad hosts.
+			(
+				"exclude = [\n\t\"batman.com\",\n\t\"robin\",\n\t\"-joker.com\",\n]\n",
+				"line 1, column 11: invalid host(s)/pattern(s): robin, -joker.com",
+			),
+			// Bad profiles.
+			("profile = 5\n", "line 1, column 11: profile must be a table"),
+			("[profile]\nkiosk = true\n", "line 2, column 9: profile.kiosk must be a table"),
+			(
+				"[profile.kiosk]\ncompact = 1\n",
+				"line 2, column 11: invalid type: integer `1`, expected a boolean",
+			),
+			// Bad TOML.
+			("backup = \n", "line 1, column 10: "),
+		] {
+			let Err(e) = validate(raw) else { panic!("Expected an error: {raw}"); };
+			assert!(e.starts_with(expected), "Expected {expected:?}, got {e:?}.");
+		}
+	}
+}
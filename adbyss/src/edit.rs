@@ -0,0 +1,355 @@
+/*!
+# Adbyss: List Edits
+*/
+
+use crate::AdbyssError;
+use adbyss_psl::Domain;
+use std::path::{
+	Path,
+	PathBuf,
+};
+use toml_edit::{
+	Array,
+	DocumentMut,
+	Item,
+	Value,
+};
+
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// # List Edit.
+///
+/// This covers the `allow`, `block`, `unallow`, and `unblock` commands, which
+/// add or remove hosts from the config's `exclude` and `include` lists.
+pub(super) enum ListEdit {
+	/// # Add to Exclude (and Remove from Include).
+	Allow,
+
+	/// # Add to Include (and Remove from Exclude).
+	Block,
+
+	/// # Remove from Exclude.
+	Unallow,
+
+	/// # Remove from Include.
+	Unblock,
+}
+
+impl ListEdit {
+	/// # From Command Name.
+	pub(super) fn from_name(src: &str) -> Option<Self> {
+		match src {
+			"allow" => Some(Self::Allow),
+			"block" => Some(Self::Block),
+			"unallow" => Some(Self::Unallow),
+			"unblock" => Some(Self::Unblock),
+			_ => None,
+		}
+	}
+
+	/// # As Str.
+	pub(super) const fn as_str(self) -> &'static str {
+		match self {
+			Self::Allow => "allow",
+			Self::Block => "block",
+			Self::Unallow => "unallow",
+			Self::Unblock => "unblock",
+		}
+	}
+
+	/// # Add To.
+	///
+	/// The list hosts are added to, if any.
+	const fn add_to(self) -> Option<&'static str> {
+		match self {
+			Self::Allow => Some("exclude"),
+			Self::Block => Some("include"),
+			Self::Unallow | Self::Unblock => None,
+		}
+	}
+
+	/// # Remove From.
+	///
+	/// The list hosts are removed from.
+	pub(super) const fn remove_from(self) -> &'static str {
+		match self {
+			Self::Allow | Self::Unblock => "include",
+			Self::Block | Self::Unallow => "exclude",
+		}
+	}
+}
+
+impl ListEdit {
+	/// # Apply to File.
+	///
+	/// Apply the edit to the config file, creating it — from the default
+	/// template — if it doesn't exist yet.
+	///
+	/// Returns `true` if the file was changed.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the file cannot be read, parsed, or
+	/// written.
+	pub(super) fn apply_file(self, src: &Path, hosts: &[Domain])
+	-> Result<bool, AdbyssError> {
+		let raw = match std::fs::read_to_string(src) {
+			Ok(raw) => raw,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => crate::init::render()?,
+			Err(e) => return Err(AdbyssError::Read(src.to_string_lossy().into_owned(), e.to_string())),
+		};
+
+		let Some(out) = self.apply(&raw, hosts)? else { return Ok(false); };
+		crate::write::write_to_file(src, out.as_bytes())?;
+		Ok(true)
+	}
+
+	/// # Held Elsewhere.
+	///
+	/// Only the main config file is edited, but the `remove_from` list might
+	/// have entries in its drop-ins too. This returns any of the hosts found
+	/// there, along with the file holding them, so the user can be told.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the drop-ins cannot be read.
+	pub(super) fn held_elsewhere(self, main: &Path, hosts: &[Domain])
+	-> Result<Vec<(String, PathBuf)>, AdbyssError> {
+		let key = self.remove_from();
+		let mut out = Vec::new();
+		for file in crate::config::dropins(&main.with_extension("d"))? {
+			let raw = std::fs::read_to_string(&file)
+				.map_err(|e| AdbyssError::Read(file.to_string_lossy().into_owned(), e.to_string()))?;
+			let Some(arr) = raw.parse::<DocumentMut>().ok()
+				.and_then(|doc| doc.get(key).and_then(Item::as_array).cloned())
+			else { continue; };
+
+			for entry in arr.iter().filter_map(Value::as_str) {
+				if let Some(host) = hosts.iter().find(|h| same_host(entry, h)) {
+					out.push((host.as_str().to_owned(), file.clone()));
+				}
+			}
+		}
+		Ok(out)
+	}
+
+	/// # Apply.
+	///
+	/// Apply the edit to the raw TOML, preserving its comments and formatting.
+	/// Returns the updated TOML, or `None` if nothing changed.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the TOML cannot be parsed, or the list in
+	/// question isn't a list.
+	fn apply(self, raw: &str, hosts: &[Domain]) -> Result<Option<String>, AdbyssError> {
+		let mut doc = raw.parse::<DocumentMut>()
+			.map_err(|e| AdbyssError::Parse(e.message().to_owned()))?;
+		let mut changed = false;
+
+		// Remove first.
+		let key = self.remove_from();
+		if let Some(item) = doc.get_mut(key) {
+			let arr = item.as_array_mut().ok_or_else(|| not_array(key))?;
+			for idx in (0..arr.len()).rev() {
+				if arr.get(idx).and_then(Value::as_str).is_some_and(|v| hosts.iter().any(|h| same_host(v, h))) {
+					remove(arr, idx);
+					changed = true;
+				}
+			}
+			if changed && arr.is_empty() {
+				arr.set_trailing("");
+				arr.set_trailing_comma(false);
+			}
+		}
+
+		// Then add.
+		if let Some(key) = self.add_to() {
+			let item = doc.entry(key).or_insert_with(|| Item::Value(Value::Array(Array::new())));
+			let arr = item.as_array_mut().ok_or_else(|| not_array(key))?;
+			for host in hosts {
+				if arr.iter().any(|v| v.as_str().is_some_and(|v| same_host(v, host))) { continue; }
+				push(arr, host.as_str());
+				changed = true;
+			}
+		}
+
+		Ok(changed.then(|| doc.to_string()))
+	}
+}
+
+
+
+/// # Not an Array Error.
+fn not_array(key: &str) -> AdbyssError {
+	AdbyssError::Parse(format!("{key} must be a list"))
+}
+
+/// # Prefix.
+fn prefix(value: &Value) -> &str {
+	value.decor().prefix().and_then(|p| p.as_str()).unwrap_or("")
+}
+
+/// # Push.
+///
+/// Append a host to the list, matching the formatting of the existing entries
+/// (so multi-line lists stay multi-line).
+fn push(arr: &mut Array, host: &str) {
+	let indent = arr.iter().last()
+		.and_then(|v| prefix(v).rsplit_once('\n'))
+		.map(|(_, indent)| indent.to_owned());
+
+	// Inline lists are easy.
+	let Some(indent) = indent else {
+		arr.push(host);
+		return;
+	};
+
+	// Any comment trailing the (former) last entry needs to stay with it.
+	let (head, rest) = split_head(arr.trailing().as_str().unwrap_or(""));
+	let head = if head.is_empty() { "\n" } else { head };
+	let mut value = Value::from(host);
+	value.decor_mut().set_prefix(format!("{head}{indent}"));
+	let rest = format!("\n{rest}");
+	arr.set_trailing(rest);
+	arr.push_formatted(value);
+}
+
+/// # Remove.
+///
+/// Remove the entry at `idx`, along with its trailing comment, if any, while
+/// keeping the one belonging to the entry before it.
+///
+/// (Comments trailing an entry are stored at the start of the following
+/// entry's prefix, or the array's trailing whitespace if it was last.)
+fn remove(arr: &mut Array, idx: usize) {
+	let removed = arr.remove(idx);
+	let (head, _) = split_head(prefix(&removed));
+
+	if let Some(next) = arr.get_mut(idx) {
+		let old = prefix(next);
+		let new =
+			if old.contains('\n') { format!("{head}{}", split_head(old).1) }
+			else { prefix(&removed).to_owned() };
+		next.decor_mut().set_prefix(new);
+	}
+	else {
+		let old = arr.trailing().as_str().unwrap_or("");
+		if old.contains('\n') {
+			let new = format!("{head}{}", split_head(old).1);
+			arr.set_trailing(new);
+		}
+	}
+}
+
+/// # Split Head.
+///
+/// Split whitespace/comments into the part through the first line break —
+/// i.e. whatever trails the preceding entry — and everything after.
+fn split_head(src: &str) -> (&str, &str) {
+	src.find('\n').map_or(("", src), |pos| src.split_at(pos + 1))
+}
+
+/// # Same Host?
+///
+/// Entries are compared in normalized form, if they're valid hosts.
+fn same_host(entry: &str, host: &Domain) -> bool {
+	entry == host.as_str() || Domain::new(entry).is_some_and(|d| d == *host)
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	const RAW: &str = r#"# Hosts.
+backup = true
+
+# Never block these.
+exclude = [
+	"ads.example.com", # Needed for work.
+	"Tracker.Example.com",
+]
+
+[categories]
+social = true
+"#;
+
+	#[test]
+	fn t_apply() {
+		let ads = Domain::new("ADS.example.com").expect("Invalid domain.");
+		let tracker = Domain::new("tracker.example.com").expect("Invalid domain.");
+		let other = Domain::new("other.example.com").expect("Invalid domain.");
+		let apply = |edit: ListEdit, raw: &str, dom: &Domain| edit
+			.apply(raw, std::slice::from_ref(dom))
+			.expect("Edit failed.");
+
+		// Allowing what's already allowed is a no-op.
+		assert_eq!(apply(ListEdit::Allow, RAW, &ads), None);
+
+		// New entries should follow the existing formatting.
+		assert_eq!(
+			apply(ListEdit::Allow, RAW, &other),
+			Some(RAW.replace(
+				"\t\"Tracker.Example.com\",\n",
+				"\t\"Tracker.Example.com\",\n\t\"other.example.com\",\n",
+			)),
+		);
+		assert_eq!(
+			apply(ListEdit::Allow, "exclude = [\n\t\"ads.example.com\", # Work.\n]\n", &other).as_deref(),
+			Some("exclude = [\n\t\"ads.example.com\", # Work.\n\t\"other.example.com\",\n]\n"),
+		);
+		assert_eq!(
+			apply(ListEdit::Allow, "exclude = [\"ads.example.com\"]\n", &other).as_deref(),
+			Some("exclude = [\"ads.example.com\", \"other.example.com\"]\n"),
+		);
+
+		// Removals should take their own comments with them, but leave the
+		// others alone.
+		assert_eq!(
+			apply(ListEdit::Unallow, RAW, &tracker),
+			Some(RAW.replace("\t\"Tracker.Example.com\",\n", "")),
+		);
+		assert_eq!(
+			apply(ListEdit::Unallow, RAW, &ads),
+			Some(RAW.replace("\t\"ads.example.com\", # Needed for work.\n", "")),
+		);
+		assert_eq!(
+			apply(ListEdit::Unallow, "exclude = [\"ads.example.com\", \"other.example.com\"]\n", &ads).as_deref(),
+			Some("exclude = [\"other.example.com\"]\n"),
+		);
+
+		// Blocking should move the host from one list to the other.
+		let out = ListEdit::Block.apply(RAW, &[ads, tracker])
+			.expect("Block failed.")
+			.expect("Block changed nothing.");
+		assert!(out.contains("exclude = []\n"));
+		assert!(out.contains("include = [\"ads.example.com\", \"tracker.example.com\"]\n"));
+		assert!(out.ends_with("[categories]\nsocial = true\n"));
+
+		// Unblocking what isn't blocked is a no-op.
+		assert_eq!(apply(ListEdit::Unblock, RAW, &other), None);
+
+		// Non-lists are an error.
+		assert!(ListEdit::Allow.apply("exclude = true", &[other]).is_err());
+	}
+
+	#[test]
+	fn t_held_elsewhere() {
+		let main = Path::new("skel/dropin.toml");
+		let metrics = Domain::new("metrics.example.org").expect("Invalid domain.");
+		let other = Domain::new("other.example.com").expect("Invalid domain.");
+		let hosts = [metrics, other];
+
+		// The first drop-in excludes one of these.
+		assert_eq!(
+			ListEdit::Unallow.held_elsewhere(main, &hosts).expect("Drop-ins failed."),
+			[("metrics.example.org".to_owned(), PathBuf::from("skel/dropin.d/10-first.toml"))],
+		);
+
+		// But doesn't include either.
+		assert!(ListEdit::Unblock.held_elsewhere(main, &hosts).expect("Drop-ins failed.").is_empty());
+	}
+}
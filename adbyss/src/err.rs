@@ -2,7 +2,6 @@
 # Adbyss: Errors
 */
 
-use crate::Source;
 use fyi_msg::fyi_ansi::{
 	ansi,
 	csi,
@@ -27,30 +26,244 @@ const HELP: &str = concat!(r#"
    ___)( )(___
 
 USAGE:
-    adbyss [FLAGS] [OPTIONS]
+    adbyss [COMMAND] [FLAGS] [OPTIONS]
+
+COMMANDS:
+    update             Download the sources and update the hostfile. (This is
+                       the default.)
+    disable            Remove *all* Adbyss entries from the hostfile.
+    show               Print a sorted blackholable hosts list to STDOUT, one
+                       per line.
+    status             Report whether Adbyss is active, when it last ran, how
+                       many hosts are blocked, and whether the section has been
+                       edited by hand.
+    check <host>       Report whether or not the host would be blocked, and by
+                       which sources and categories.
+    diff               Preview the hosts an update would add or remove.
+    restore            Put back the hostfile backup from the last write.
+    allow <host>…      Never block the host(s).
+    block <host>…      Always block the host(s).
+    unallow <host>…    Undo an allow.
+    unblock <host>…    Undo a block.
+    help [command]     Print help information for the command.
 
 FLAGS:
-        --disable      Remove *all* Adbyss entries from the hostfile.
+        --check-config Validate the configuration file(s), reporting any
+                       problems, then exit.
+        --force        Allow --init to overwrite an existing file.
     -h, --help         Prints help information.
+        --init [path]  Write a fully-commented config reflecting the current
+                       defaults to the path (/etc/adbyss.toml by default, or
+                       - for STDOUT), then exit.
+        --no-escalate  Fail instead of restarting with sudo (or doas, run0,
+                       pkexec) when root privileges are needed.
+        --print-config Print the effective configuration — the main file
+                       merged with any drop-ins — noting where each value
+                       came from.
     -q, --quiet        Do *not* summarize changes after write.
-        --show         Print a sorted blackholable hosts list to STDOUT, one per
-                       line.
-        --stdout       Print the would-be hostfile to STDOUT instead of writing
-                       it to disk.
+        --resume       Restore the Adbyss entries set aside by --pause.
     -V, --version      Prints version information.
     -y, --yes          Non-interactive mode; answer "yes" to all prompts.
 
 OPTIONS:
     -c, --config <path>    Use this configuration instead of /etc/adbyss.toml.
+        --pause <dur>      Temporarily remove the Adbyss entries for a duration
+                           like 90s, 15m, 2h, or 1d. They will be restored
+                           automatically afterwards (or sooner with --resume).
+        --profile <name>   Layer the named [profile.<name>] config section on
+                           top of the base settings. (This can also be set
+                           via the ADBYSS_PROFILE environment variable.)
+        --report <fmt>     Print a machine-readable summary of the update to
+                           STDOUT once it's done. The only format is json.
+                           (Implies --quiet.)
+
+OVERRIDES:
+    Any of the config settings can also be set from the command line, layered
+    on top of everything else. Options marked (+) may be repeated, and append
+    to the configured list rather than replace it.
+
+        --backup / --no-backup
+        --compact / --no-compact
+        --protect-defaults / --no-protect-defaults
+        --protect-strict / --no-protect-strict
+        --allow-suffix <sfx>    (+)
+        --block-suffix <sfx>    (+) Wildcard show formats only.
+        --category <list>       Enable or disable categories, e.g. +social,-ads.
+        --cname-source <src>    (+)
+        --exclude <host>        (+)
+        --hostfile <path>
+        --include <host>        (+)
+        --post-write <hook>     (+)
+        --protect <host>        (+)
+        --regexclude <re>       (+)
+        --regexinclude <re>     (+)
+        --rejected-file <path>
+        --report-path <path>
+        --source <list>         Enable or disable sources (adaway, adbyss,
+                                stevenblack, yoyo), e.g. +adaway,-yoyo.
+        --www-expansion <mode>  none, strip, or both.
+
+DEPRECATED:
+    The following flags still work, but have been replaced by commands.
+
+        --check <host>     Use check <host> instead.
+        --disable          Use disable instead.
+        --show             Use show instead.
+        --status           Use status instead.
+
+EXIT CODES:
+    0     Success.
+    2     Invalid/unknown option.
+    3     Conflicting commands.
+    4     Aborted by the user.
+    10    Invalid configuration.
+    11    Unknown configuration profile.
+    12    Protected hosts in the blocklist (strict mode).
+    20    Unable to read file.
+    21    Unable to write file.
+    22    File already exists.
+    23    Root privileges required.
+    24    Escalated process killed by a signal.
+    30    No internet connection.
+    31    Unable to fetch source.
+    40    No domains to blackhole.
+    41    No Adbyss section in the hostfile.
+    42    No backup to restore.
+    43    Post-write hook failed.
 
 SOURCES:
     AdAway:       <https://adaway.org/>
     Steven Black: <https://github.com/StevenBlack/hosts>
     Yoyo:         <https://pgl.yoyo.org/adservers/>
 
-Additional global settings are stored in /etc/adbyss.toml.
+Additional global settings are stored in /etc/adbyss.toml, and any *.toml
+drop-ins in /etc/adbyss.d, which are merged in lexical order.
 "#);
 
+/// # Help Text (Check).
+pub(super) const HELP_CHECK: &str = "
+USAGE:
+    adbyss check <host> [FLAGS] [OPTIONS]
+
+Report whether or not the host would be blocked, and if so, by which sources
+and under which categories, and — for CNAME-cloaked hosts — which tracker it
+points to, if known. The hostfile is left alone.
+
+The config, --profile, and overrides all apply, as for update.
+";
+
+/// # Help Text (Diff).
+pub(super) const HELP_DIFF: &str = "
+USAGE:
+    adbyss diff [FLAGS] [OPTIONS]
+
+Build the blocklist, then print the hosts an update would add (+) or remove
+(-) from the hostfile, one per line. The hostfile is left alone.
+
+The config, --profile, and overrides all apply, as for update.
+";
+
+/// # Help Text (Disable).
+pub(super) const HELP_DISABLE: &str = "
+USAGE:
+    adbyss disable [FLAGS] [OPTIONS]
+
+Remove *all* Adbyss entries from the hostfile, and cancel any pending pause.
+
+FLAGS:
+    -y, --yes          Non-interactive mode; answer \"yes\" to all prompts.
+";
+
+/// # Help Text (Allow, Block, etc.).
+pub(super) const HELP_EDIT: &str = "
+USAGE:
+    adbyss allow <host>… [FLAGS] [OPTIONS]
+    adbyss block <host>… [FLAGS] [OPTIONS]
+    adbyss unallow <host>… [FLAGS] [OPTIONS]
+    adbyss unblock <host>… [FLAGS] [OPTIONS]
+
+Edit the include/exclude lists in the main config file (/etc/adbyss.toml, or
+the one passed with -c), preserving its comments and formatting. If the file
+doesn't exist yet, it will be created.
+
+    allow <host>…      Never block the host(s). (Adds them to exclude, and
+                       removes them from include.)
+    block <host>…      Always block the host(s). (Adds them to include, and
+                       removes them from exclude.)
+    unallow <host>…    Remove the host(s) from exclude.
+    unblock <host>…    Remove the host(s) from include.
+
+FLAGS:
+        --update       Update the hostfile straight away.
+
+OPTIONS:
+    -c, --config <path>    Edit this configuration instead of /etc/adbyss.toml.
+        --report <fmt>     Print a machine-readable summary of the --update
+                           to STDOUT once it's done. The only format is json.
+";
+
+/// # Help Text (Restore).
+pub(super) const HELP_RESTORE: &str = "
+USAGE:
+    adbyss restore [FLAGS] [OPTIONS]
+
+Replace the hostfile with the backup saved by the last write (e.g.
+/etc/hosts.adbyss.bak), and cancel any pending pause. (Backups are only made
+when the backup setting is enabled.)
+
+FLAGS:
+    -y, --yes          Non-interactive mode; answer \"yes\" to all prompts.
+";
+
+/// # Help Text (Show).
+pub(super) const HELP_SHOW: &str = "
+USAGE:
+    adbyss show [FLAGS] [OPTIONS]
+
+Print a sorted blackholable hosts list to STDOUT, one per line. The hostfile
+is left alone.
+
+OPTIONS:
+        --format <fmt>     Print the list as plain (default), adblock, csv,
+                           dnsmasq, json, rpz, or unbound. (Redundant
+                           subdomains are collapsed for the adblock, dnsmasq,
+                           rpz, and unbound formats.)
+
+The config, --profile, and overrides all apply, as for update.
+";
+
+/// # Help Text (Status).
+pub(super) const HELP_STATUS: &str = "
+USAGE:
+    adbyss status [OPTIONS]
+
+Report whether Adbyss is active, when it last ran, how many hosts are blocked,
+and whether the section has been edited by hand, or the config has changed
+since.
+";
+
+/// # Help Text (Update).
+pub(super) const HELP_UPDATE: &str = "
+USAGE:
+    adbyss [update] [FLAGS] [OPTIONS]
+
+Download the sources, build the blocklist, and write it to the hostfile. This
+is what Adbyss does when no other command is given.
+
+FLAGS:
+    -q, --quiet        Do *not* summarize changes after write.
+        --stdout       Print the would-be hostfile to STDOUT instead of writing
+                       it to disk.
+    -y, --yes          Non-interactive mode; answer \"yes\" to all prompts.
+
+OPTIONS:
+        --report <fmt> Print a machine-readable summary of the update to
+                       STDOUT once it's done. The only format is json.
+                       (Implies --quiet.)
+
+Run adbyss --help for the config options and overrides.
+";
+
 
 
 #[derive(Debug, Clone)]
@@ -59,9 +272,26 @@ pub(super) enum AdbyssError {
 	/// # Early Abort.
 	Aborted,
 
+	/// # Conflicting Commands.
+	Conflict(String),
+
+	/// # File Exists.
+	Exists(String),
+
+	/// # Adbyss Isn't Active.
+	Inactive,
+
 	/// # Invalid CLI Argument.
 	InvalidCli(String),
 
+	/// # Escalated Process Killed.
+	///
+	/// This holds the signal.
+	Killed(String),
+
+	/// # No Backup.
+	NoBackup(String),
+
 	/// # No Internet.
 	NoInternet,
 
@@ -71,20 +301,39 @@ pub(super) enum AdbyssError {
 	/// # Deserialization Error.
 	Parse(String),
 
+	/// # Unknown Profile.
+	Profile(String),
+
+	/// # Post-Write Hook(s) Failed.
+	PostWrite(String),
+
+	/// # Protected Domain(s) in Shitlist (Strict Mode).
+	Protected(String),
+
 	/// # Root required.
-	Root,
+	Root(String),
 
 	/// # Read Issue.
-	Read(String),
+	///
+	/// This holds the path and the underlying I/O error, e.g. "Permission
+	/// denied (os error 13)".
+	Read(String, String),
 
 	/// # Unable to Fetch Source.
-	SourceFetch(Source),
+	///
+	/// This holds the source name (or URL) and the reason, e.g. the HTTP
+	/// status.
+	SourceFetch(String, String),
 
 	/// # Write Issue.
-	Write(String),
+	///
+	/// This holds the path and the underlying I/O error.
+	Write(String, String),
 
 	/// # Print Help (Not an Error).
-	PrintHelp,
+	///
+	/// This holds the command-specific help, if any.
+	PrintHelp(Option<&'static str>),
 
 	/// # Print Version (Not an Error).
 	PrintVersion,
@@ -95,12 +344,7 @@ impl std::error::Error for AdbyssError {}
 impl fmt::Display for AdbyssError {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		f.write_str(self.as_str())?;
-		match self {
-			Self::InvalidCli(s) | Self::Parse(s) | Self::Read(s) | Self::Write(s) =>
-				write!(f, dim!(" ({})"), s),
-			Self::SourceFetch(s) => write!(f, dim!(" ({})"), s.as_str()),
-			_ => Ok(()),
-		}
+		self.detail().map_or(Ok(()), |detail| write!(f, dim!(" ({})"), detail))
 	}
 }
 
@@ -109,16 +353,118 @@ impl AdbyssError {
 	pub(super) const fn as_str(&self) -> &'static str {
 		match self {
 			Self::Aborted => "Operation aborted.",
+			Self::Conflict(_) => "Only one command can be run at a time.",
+			Self::Exists(_) => "Refusing to overwrite existing file; use --force to replace it.",
+			Self::Inactive => "There is no Adbyss section in the hostfile.",
 			Self::InvalidCli(_) => "Invalid/unknown option.",
+			Self::Killed(_) => "The escalated process was killed by a signal.",
+			Self::NoBackup(_) => "There is no backup to restore.",
 			Self::NoInternet => "No internet connection available.",
 			Self::NoShitlist => "There are no domains to blackhole!",
 			Self::Parse(_) => "Parsing failed.",
-			Self::Read(_) => "Unable to read file.",
-			Self::Root => "Adbyss requires root privileges.",
-			Self::SourceFetch(_) => "Unable to fetch source.",
-			Self::Write(_) => "Unable to write file.",
-			Self::PrintHelp => HELP,
+			Self::PostWrite(_) => "Post-write hook failed.",
+			Self::Profile(_) => "Unknown configuration profile.",
+			Self::Protected(_) => "Protected hosts turned up in the blocklist.",
+			Self::Read(..) => "Unable to read file.",
+			Self::Root(_) => "Adbyss requires root privileges.",
+			Self::SourceFetch(..) => "Unable to fetch source.",
+			Self::Write(..) => "Unable to write file.",
+			Self::PrintHelp(None) => HELP,
+			Self::PrintHelp(Some(s)) => s,
 			Self::PrintVersion => concat!("Adbyss v", env!("CARGO_PKG_VERSION")),
 		}
 	}
 }
+
+impl AdbyssError {
+	/// # Detail.
+	///
+	/// Return the specifics — the path, host, reason, etc. — if any.
+	pub(super) fn detail(&self) -> Option<String> {
+		match self {
+			Self::Conflict(s) |
+			Self::Exists(s) |
+			Self::InvalidCli(s) |
+			Self::Killed(s) |
+			Self::NoBackup(s) |
+			Self::Parse(s) |
+			Self::PostWrite(s) |
+			Self::Profile(s) |
+			Self::Protected(s) |
+			Self::Root(s) => Some(s.clone()),
+			Self::Read(s, e) |
+			Self::SourceFetch(s, e) |
+			Self::Write(s, e) => Some(format!("{s}: {e}")),
+			_ => None,
+		}
+	}
+
+	/// # Exit Code.
+	///
+	/// Each error gets its own (stable) exit code so scripts and monitors
+	/// can tell them apart. These are documented in the help screen and
+	/// manual.
+	pub(super) const fn exit_code(&self) -> u8 {
+		match self {
+			Self::PrintHelp(_) | Self::PrintVersion => 0,
+			Self::InvalidCli(_) => 2,
+			Self::Conflict(_) => 3,
+			Self::Aborted => 4,
+			Self::Parse(_) => 10,
+			Self::Profile(_) => 11,
+			Self::Protected(_) => 12,
+			Self::Read(..) => 20,
+			Self::Write(..) => 21,
+			Self::Exists(_) => 22,
+			Self::Root(_) => 23,
+			Self::Killed(_) => 24,
+			Self::NoInternet => 30,
+			Self::SourceFetch(..) => 31,
+			Self::NoShitlist => 40,
+			Self::Inactive => 41,
+			Self::NoBackup(_) => 42,
+			Self::PostWrite(_) => 43,
+		}
+	}
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::collections::BTreeSet;
+
+	#[test]
+	fn t_exit_code() {
+		let all = [
+			AdbyssError::Aborted,
+			AdbyssError::Conflict(String::new()),
+			AdbyssError::Exists(String::new()),
+			AdbyssError::Inactive,
+			AdbyssError::InvalidCli(String::new()),
+			AdbyssError::Killed(String::new()),
+			AdbyssError::NoBackup(String::new()),
+			AdbyssError::NoInternet,
+			AdbyssError::NoShitlist,
+			AdbyssError::Parse(String::new()),
+			AdbyssError::PostWrite(String::new()),
+			AdbyssError::Profile(String::new()),
+			AdbyssError::Protected(String::new()),
+			AdbyssError::Read(String::new(), String::new()),
+			AdbyssError::Root(String::new()),
+			AdbyssError::SourceFetch(String::new(), String::new()),
+			AdbyssError::Write(String::new(), String::new()),
+		];
+
+		// Real errors should all have their own non-zero code, and each one
+		// should be documented.
+		let mut seen = BTreeSet::new();
+		for e in all {
+			let code = e.exit_code();
+			assert_ne!(code, 0, "{e}");
+			assert!(seen.insert(code), "Duplicate exit code: {code}");
+			assert!(HELP.contains(&format!("\n    {code:<6}")), "Undocumented exit code: {code}");
+		}
+	}
+}
@@ -0,0 +1,844 @@
+/*!
+# Adbyss: Filters
+*/
+
+use adbyss_psl::Domain;
+use crate::source::Provenance;
+use regex::RegexSet;
+use serde::{
+	de,
+	Deserialize,
+};
+use std::{
+	borrow::Cow,
+	cell::Cell,
+	collections::BTreeSet,
+	fmt,
+	str::FromStr,
+};
+
+
+
+/// # Built-in Protected Hosts.
+///
+/// Blocking any of these would likely break the system (or the fleet), so
+/// they're dropped from the shitlist unless explicitly included. The
+/// `protect_defaults` setting can be used to disable this list.
+const PROTECTED: [&str; 20] = [
+	// Code and packages.
+	"*.crates.io",
+	"*.docker.com",
+	"*.docker.io",
+	"*.github.com",
+	"*.githubassets.com",
+	"@suffix:githubusercontent.com",
+	"*.gitlab.com",
+	"*.npmjs.org",
+	"*.pypi.org",
+	"*.pythonhosted.org",
+	"*.rust-lang.org",
+
+	// OS updates.
+	"*.archlinux.org",
+	"*.debian.org",
+	"*.fedoraproject.org",
+	"*.ubuntu.com",
+
+	// Certificates.
+	"*.letsencrypt.org",
+
+	// Sign-on.
+	"accounts.google.com",
+	"login.microsoftonline.com",
+	"*.okta.com",
+	"*.auth0.com",
+];
+
+/// # TLD Pattern Prefix.
+const PREFIX_TLD: &str = "@tld:";
+
+/// # Suffix Pattern Prefix.
+const PREFIX_SUFFIX: &str = "@suffix:";
+
+
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+/// # Host Pattern.
+///
+/// The `include` and `exclude` settings accept the following forms:
+///
+/// | Pattern | Matches |
+/// | ------- | ------- |
+/// | `example.com` | `example.com` |
+/// | `*.example.com` | `example.com` and all of its subdomains. |
+/// | `ads.*.example.com` | `ads.<any single label>.example.com` |
+/// | `@tld:example.com` | Any host whose [`Domain::tld`] is `example.com`. |
+/// | `@suffix:xyz` | Any host whose [`Domain::suffix`] is `xyz`. |
+pub(super) enum Pattern {
+	/// # Exact Match.
+	Exact(Domain),
+
+	/// # Domain and Children.
+	Tree(Domain),
+
+	/// # Labels, With `*` Wildcards.
+	Glob(Vec<String>),
+
+	/// # By TLD.
+	Tld(String),
+
+	/// # By Suffix.
+	Suffix(String),
+}
+
+impl FromStr for Pattern {
+	type Err = ();
+
+	fn from_str(src: &str) -> Result<Self, Self::Err> {
+		let src = src.trim().to_ascii_lowercase();
+
+		// Prefixed forms.
+		if let Some(rest) = src.strip_prefix(PREFIX_TLD) {
+			return Domain::new(rest.trim())
+				.filter(|d| d.subdomain().is_none())
+				.map(|d| Self::Tld(d.take()))
+				.ok_or(());
+		}
+		if let Some(rest) = src.strip_prefix(PREFIX_SUFFIX) {
+			return parse_suffix(rest).map(Self::Suffix).ok_or(());
+		}
+
+		// Domain and children.
+		if let Some(rest) = src.strip_prefix("*.") && ! rest.contains('*') {
+			return Domain::new(rest).map(Self::Tree).ok_or(());
+		}
+
+		// Any other wildcards must be whole labels.
+		if src.contains('*') {
+			let labels: Vec<String> = src.split('.').map(String::from).collect();
+			if labels.iter().any(|l| l != "*" && l.contains('*')) { return Err(()); }
+
+			// Make sure the result would otherwise be a valid domain, and that
+			// the wildcards aren't standing in for any part of the TLD.
+			let test = labels.iter()
+				.map(|l| if l == "*" { "x" } else { l.as_str() })
+				.collect::<Vec<_>>()
+				.join(".");
+			let dom = Domain::new(test).ok_or(())?;
+			if src.ends_with(dom.tld()) { Ok(Self::Glob(labels)) }
+			else { Err(()) }
+		}
+		else { Domain::new(src).map(Self::Exact).ok_or(()) }
+	}
+}
+
+impl Pattern {
+	/// # Is Match?
+	fn matches(&self, dom: &Domain) -> bool {
+		match self {
+			Self::Exact(d) => d == dom,
+			Self::Tree(d) => dom.ends_with(d),
+			Self::Glob(labels) => {
+				let mut host = dom.as_str().split('.');
+				labels.iter().all(|l| host.next().is_some_and(|h| l == "*" || l == h)) &&
+				host.next().is_none()
+			},
+			Self::Tld(v) => dom.tld() == v,
+			Self::Suffix(v) => dom.suffix() == v,
+		}
+	}
+}
+
+
+
+#[derive(Debug, Clone, Default)]
+/// # Pattern Set.
+///
+/// This holds a compiled collection of [`Pattern`]s, grouped by type so that
+/// matching can be done efficiently.
+pub(super) struct Patterns {
+	/// # Exact Matches.
+	exact: BTreeSet<Domain>,
+
+	/// # Parents (Matched Along With Their Children).
+	tree: BTreeSet<String>,
+
+	/// # Wildcard Globs.
+	glob: Vec<Pattern>,
+
+	/// # TLDs.
+	tld: BTreeSet<String>,
+
+	/// # Suffixes.
+	suffix: BTreeSet<String>,
+}
+
+impl<'de> Deserialize<'de> for Patterns {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where D: de::Deserializer<'de> {
+		let mut out = Self::default();
+		let mut invalid = Vec::new();
+		for raw in Vec::<Cow<str>>::deserialize(deserializer)? {
+			match Pattern::from_str(&raw) {
+				Ok(pat) => { out.push(pat); },
+				Err(()) => { invalid.push(raw.trim().to_owned()); },
+			}
+		}
+
+		if invalid.is_empty() { Ok(out) }
+		else { Err(invalid_entries(&invalid)) }
+	}
+}
+
+impl Patterns {
+	/// # Is Empty?
+	pub(super) fn is_empty(&self) -> bool {
+		self.exact.is_empty() &&
+		self.tree.is_empty() &&
+		self.glob.is_empty() &&
+		self.tld.is_empty() &&
+		self.suffix.is_empty()
+	}
+
+	/// # Is Match?
+	pub(super) fn matches(&self, dom: &Domain) -> bool {
+		self.exact.contains(dom) ||
+		(! self.tree.is_empty() && parents(dom).any(|p| self.tree.contains(p))) ||
+		self.tld.contains(dom.tld()) ||
+		self.suffix.contains(dom.suffix()) ||
+		self.glob.iter().any(|p| p.matches(dom))
+	}
+
+	/// # Built-in Protected Hosts.
+	pub(super) fn protected() -> Self {
+		let mut out = Self::default();
+		for raw in PROTECTED {
+			if let Ok(pat) = Pattern::from_str(raw) { out.push(pat); }
+		}
+		out
+	}
+
+	/// # Extend.
+	pub(super) fn extend(&mut self, other: &Self) {
+		self.exact.extend(other.exact.iter().cloned());
+		self.tree.extend(other.tree.iter().cloned());
+		for pat in &other.glob {
+			if ! self.glob.contains(pat) { self.glob.push(pat.clone()); }
+		}
+		self.tld.extend(other.tld.iter().cloned());
+		self.suffix.extend(other.suffix.iter().cloned());
+	}
+
+	/// # Expand WWW.
+	///
+	/// Add the www/non-www counterparts of any exact matches, per the user's
+	/// preference. (The other pattern types are left as-is.)
+	pub(super) fn expand_www(&mut self, www: WwwExpansion) {
+		let extra: Vec<Domain> = self.exact.iter().filter_map(|d| www.expand(d)).collect();
+		self.exact.extend(extra);
+	}
+
+	/// # Push.
+	pub(super) fn push(&mut self, pat: Pattern) {
+		match pat {
+			Pattern::Exact(d) => { self.exact.insert(d); },
+			Pattern::Tree(d) => { self.tree.insert(d.take()); },
+			Pattern::Glob(_) => if ! self.glob.contains(&pat) { self.glob.push(pat); },
+			Pattern::Tld(v) => { self.tld.insert(v); },
+			Pattern::Suffix(v) => { self.suffix.insert(v); },
+		}
+	}
+}
+
+
+
+#[derive(Debug, Clone, Default)]
+/// # Inclusions.
+///
+/// Plain hosts are simply added to the shitlist. Patterns can't conjure hosts
+/// out of thin air, so instead apply to the hosts found in the sources: any
+/// match is kept even if an exclude rule would otherwise remove it. (The
+/// parent of a `*.example.com` or `@tld:example.com` pattern is also added.)
+pub(super) struct Include {
+	/// # Hosts to Add.
+	hosts: Vec<String>,
+
+	/// # Hosts to Keep.
+	keep: Patterns,
+}
+
+impl<'de> Deserialize<'de> for Include {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where D: de::Deserializer<'de> {
+		let mut out = Self::default();
+		let mut invalid = Vec::new();
+		for mut raw in Vec::<String>::deserialize(deserializer)? {
+			raw.make_ascii_lowercase();
+			let trimmed = raw.trim();
+
+			// Patterns.
+			if trimmed.starts_with('@') || trimmed.contains('*') {
+				let Ok(pat) = Pattern::from_str(trimmed) else {
+					invalid.push(trimmed.to_owned());
+					continue;
+				};
+				match &pat {
+					Pattern::Tree(d) => { out.hosts.push(d.as_str().to_owned()); },
+					Pattern::Tld(v) => { out.hosts.push(v.clone()); },
+					_ => {},
+				}
+				out.keep.push(pat);
+			}
+			// Plain hosts. (Their www/non-www counterparts are added later.)
+			else if let Some(dom) = Domain::new(trimmed) { out.hosts.push(dom.take()); }
+			else { invalid.push(trimmed.to_owned()); }
+		}
+
+		if ! invalid.is_empty() { return Err(invalid_entries(&invalid)); }
+
+		// Sort and dedupe.
+		out.hosts.sort_unstable();
+		out.hosts.dedup();
+
+		Ok(out)
+	}
+}
+
+impl Include {
+	/// # Hosts to Add.
+	pub(super) fn hosts(&self) -> &[String] { &self.hosts }
+
+	/// # Keep?
+	///
+	/// Returns `true` if the host matches one of the include patterns.
+	pub(super) fn keep(&self, dom: &Domain) -> bool { self.keep.matches(dom) }
+}
+
+
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// # WWW Expansion.
+///
+/// This determines whether or not the www/non-www counterparts of hosts are
+/// added alongside them.
+pub(super) enum WwwExpansion {
+	/// # Leave Hosts As-Is.
+	None,
+
+	#[default]
+	/// # Add non-www Counterparts of www Hosts.
+	Strip,
+
+	/// # Add Both Counterparts.
+	Both,
+}
+
+impl WwwExpansion {
+	/// # Expand.
+	///
+	/// Return the counterpart of the host, if any.
+	pub(super) fn expand(self, dom: &Domain) -> Option<Domain> {
+		match self {
+			Self::None => None,
+			Self::Strip => dom.without_www(),
+			Self::Both => dom.without_www().or_else(|| Domain::new(format!("www.{dom}"))),
+		}
+	}
+}
+
+
+
+#[derive(Clone, Default)]
+/// # Regular Expressions.
+///
+/// This holds the `regexclude` or `regexinclude` patterns, along with a
+/// running count of the hosts each has matched.
+pub(super) struct Regexes {
+	/// # Patterns.
+	set: Option<RegexSet>,
+
+	/// # Hits (Per Pattern).
+	hits: Vec<Cell<usize>>,
+}
+
+impl fmt::Debug for Regexes {
+	/// # Debug.
+	///
+	/// Only the patterns are included; the hit counts are run-specific.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_list().entries(self.patterns()).finish()
+	}
+}
+
+impl<'de> Deserialize<'de> for Regexes {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where D: de::Deserializer<'de> {
+		let set = RegexSet::new(Vec::<Cow<str>>::deserialize(deserializer)?)
+			.map_err(de::Error::custom)?;
+		if set.is_empty() { Ok(Self::default()) }
+		else {
+			let hits = vec![Cell::new(0); set.len()];
+			Ok(Self { set: Some(set), hits })
+		}
+	}
+}
+
+impl Regexes {
+	/// # Is Empty?
+	pub(super) const fn is_empty(&self) -> bool { self.set.is_none() }
+
+	/// # Is Match?
+	///
+	/// Returns `true` if any of the patterns match the host, bumping their
+	/// hit counts accordingly.
+	pub(super) fn is_match(&self, host: &str) -> bool {
+		let Some(set) = &self.set else { return false; };
+		let mut out = false;
+		for idx in set.matches(host) {
+			out = true;
+			self.hits[idx].set(self.hits[idx].get() + 1);
+		}
+		out
+	}
+
+	/// # Patterns.
+	pub(super) fn patterns(&self) -> &[String] {
+		self.set.as_ref().map_or(&[], RegexSet::patterns)
+	}
+
+	/// # Stats.
+	///
+	/// Return each pattern along with the number of hosts it has matched.
+	pub(super) fn stats(&self) -> impl Iterator<Item=(&str, usize)> {
+		self.patterns().iter().map(String::as_str).zip(self.hits.iter().map(Cell::get))
+	}
+
+	/// # Reset Hits.
+	///
+	/// Zero the hit counts ahead of a (re)build.
+	pub(super) fn reset_hits(&self) {
+		for hit in &self.hits { hit.set(0); }
+	}
+}
+
+
+
+#[derive(Debug, Clone, Default)]
+/// # Public Suffixes.
+///
+/// This holds the `block_suffixes` and `allow_suffixes` settings. A suffix
+/// covers itself and any longer suffixes ending with it, so `uk` would match
+/// hosts under `co.uk` too.
+pub(super) struct Suffixes(BTreeSet<String>);
+
+impl<'de> Deserialize<'de> for Suffixes {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where D: de::Deserializer<'de> {
+		let mut out = BTreeSet::new();
+		for raw in Vec::<Cow<str>>::deserialize(deserializer)? {
+			let suffix = parse_suffix(&raw.to_ascii_lowercase())
+				.ok_or_else(|| de::Error::custom(format!("invalid suffix: {}", raw.trim())))?;
+			out.insert(suffix);
+		}
+
+		Ok(Self(out))
+	}
+}
+
+impl Suffixes {
+	/// # Is Empty?
+	pub(super) fn is_empty(&self) -> bool { self.0.is_empty() }
+
+	/// # Len.
+	pub(super) fn len(&self) -> usize { self.0.len() }
+
+	/// # Iterator.
+	pub(super) fn iter(&self) -> impl Iterator<Item=&str> { self.0.iter().map(String::as_str) }
+
+	/// # Is Match?
+	///
+	/// Returns `true` if the host falls under any of the suffixes.
+	pub(super) fn matches(&self, dom: &Domain) -> bool {
+		! self.0.is_empty() && {
+			let suffix = dom.suffix();
+			self.0.contains(suffix) ||
+			suffix.match_indices('.').any(|(k, _)| self.0.contains(&suffix[k + 1..]))
+		}
+	}
+
+	/// # Without.
+	///
+	/// Return a copy of the set minus any suffixes covered by `other`.
+	pub(super) fn without(&self, other: &Self) -> Self {
+		if other.is_empty() { return self.clone(); }
+		Self(
+			self.0.iter()
+				.filter(|s| Domain::new(format!("x.{s}")).is_none_or(|d| ! other.matches(&d)))
+				.cloned()
+				.collect()
+		)
+	}
+}
+
+
+
+/// # Collapse Redundant Subdomains.
+///
+/// For outputs where blocking a domain implicitly blocks its subdomains, any
+/// entries whose parents — or public suffixes, if blocked outright — are also
+/// in the list are redundant.
+///
+/// This removes them, returning the number of entries collapsed. The list is
+/// expected to be sorted going in, and will be sorted coming out.
+pub(super) fn collapse(list: &mut Vec<(Domain, Provenance)>, suffixes: &Suffixes)
+-> usize {
+	let before = list.len();
+	if ! suffixes.is_empty() { list.retain(|(d, _)| ! suffixes.matches(d)); }
+	if list.is_empty() { return before; }
+
+	// Sort by label, right to left, so each domain is immediately followed
+	// by all of its descendants.
+	list.sort_unstable_by(|a, b| a.0.as_str().rsplit('.').cmp(b.0.as_str().rsplit('.')));
+
+	// Keep only the entries that aren't children of the last one kept.
+	let mut kept = 0;
+	for next in 1..list.len() {
+		if ! list[next].0.ends_with(&list[kept].0) {
+			kept += 1;
+			list.swap(kept, next);
+		}
+	}
+	list.truncate(kept + 1);
+
+	// Restore the normal order.
+	list.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+	before - list.len()
+}
+
+/// # Invalid Entries Error.
+///
+/// Build a deserialization error listing all of the bad hosts/patterns at
+/// once, so they can be fixed in one go.
+fn invalid_entries<E: de::Error>(invalid: &[String]) -> E {
+	E::custom(format!("invalid host(s)/pattern(s): {}", invalid.join(", ")))
+}
+
+/// # Has Uppercase?
+///
+/// Returns `true` if the regular expression contains literal uppercase
+/// characters, which can never match our (lowercase) hosts, unless the
+/// pattern is case-insensitive.
+///
+/// Escapes like `\D` or `\p{Lu}` are ignored.
+pub(super) fn has_uppercase(src: &str) -> bool {
+	if src.contains("(?i") { return false; }
+
+	let mut chars = src.chars();
+	while let Some(c) = chars.next() {
+		// Skip escapes, and the {name} of any \p{name} classes.
+		if c == '\\' {
+			if matches!(chars.next(), Some('p' | 'P')) && chars.next() == Some('{') {
+				for c in chars.by_ref() { if c == '}' { break; } }
+			}
+		}
+		else if c.is_uppercase() { return true; }
+	}
+
+	false
+}
+
+/// # Parse Suffix.
+///
+/// Return the normalized public suffix, if valid.
+fn parse_suffix(src: &str) -> Option<String> {
+	let src = src.trim().trim_start_matches('.');
+	Domain::new(format!("x.{src}"))
+		.filter(|d| d.suffix() == src)
+		.map(|_| src.to_owned())
+}
+
+/// # Parents (Inclusive).
+///
+/// Return an iterator over the host and each of its parents, down to (and
+/// including) the TLD. For `a.b.example.com`, that would be
+/// `a.b.example.com`, `b.example.com`, and `example.com`.
+fn parents(dom: &Domain) -> impl Iterator<Item=&str> {
+	let host = dom.as_str();
+	let sub_len = host.len() - dom.tld().len();
+	std::iter::once(host).chain(
+		host.as_bytes()[..sub_len].iter()
+			.enumerate()
+			.filter_map(|(k, &b)| if b == b'.' { Some(&host[k + 1..]) } else { None })
+	)
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_parents() {
+		let dom = Domain::new("a.b.example.co.uk").unwrap();
+		assert_eq!(
+			parents(&dom).collect::<Vec<_>>(),
+			["a.b.example.co.uk", "b.example.co.uk", "example.co.uk"],
+		);
+
+		let dom = Domain::new("example.com").unwrap();
+		assert_eq!(parents(&dom).collect::<Vec<_>>(), ["example.com"]);
+	}
+
+	#[test]
+	fn t_collapse() {
+		let mut list: Vec<(Domain, Provenance)> = [
+			"a.tracker.com",
+			"ads.example.com",
+			"b.tracker.com",
+			"deep.x.a.tracker.com",
+			"example.com",
+			"nottracker.com",
+			"tracker.com",
+			"tracker.co.uk",
+			"trackerz.com",
+			"www.tracker.co.uk",
+		].into_iter()
+			.map(|d| (Domain::new(d).unwrap(), Provenance::default()))
+			.collect();
+		list.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+		assert_eq!(collapse(&mut list, &Suffixes::default()), 5);
+		let list: Vec<&str> = list.iter().map(|(d, _)| d.as_str()).collect();
+		assert_eq!(
+			list,
+			["example.com", "nottracker.com", "tracker.co.uk", "tracker.com", "trackerz.com"],
+		);
+
+		// Empty is fine too.
+		assert_eq!(collapse(&mut Vec::new(), &Suffixes::default()), 0);
+	}
+
+	#[test]
+	fn t_www() {
+		let bare = Domain::new("blobfolio.com").unwrap();
+		let www = Domain::new("www.blobfolio.com").unwrap();
+
+		assert_eq!(WwwExpansion::None.expand(&bare), None);
+		assert_eq!(WwwExpansion::None.expand(&www), None);
+		assert_eq!(WwwExpansion::Strip.expand(&bare), None);
+		assert_eq!(WwwExpansion::Strip.expand(&www), Some(bare.clone()));
+		assert_eq!(WwwExpansion::Both.expand(&bare), Some(www.clone()));
+		assert_eq!(WwwExpansion::Both.expand(&www), Some(bare.clone()));
+
+		// Excludes should pick them up too.
+		let mut ex = Patterns::default();
+		ex.push(Pattern::Exact(bare));
+		ex.expand_www(WwwExpansion::Strip);
+		assert!(! ex.matches(&www));
+		ex.expand_www(WwwExpansion::Both);
+		assert!(ex.matches(&www));
+	}
+
+	#[test]
+	fn t_regexes() {
+		#[derive(Deserialize)]
+		struct Wrapper { regexclude: Regexes }
+
+		let re: Wrapper = toml::from_str(r"regexclude = [
+			'^ads\.',
+			'\.com$',
+			'^Nope',
+		]").expect("Unable to parse regexes.");
+
+		assert!(re.regexclude.is_match("ads.example.com"));
+		assert!(re.regexclude.is_match("www.example.com"));
+		assert!(! re.regexclude.is_match("ads"));
+		assert!(! re.regexclude.is_match("example.org"));
+		assert_eq!(
+			re.regexclude.stats().collect::<Vec<_>>(),
+			[("^ads\\.", 1), ("\\.com$", 2), ("^Nope", 0)],
+		);
+
+		// The counts shouldn't turn up in the debug output.
+		assert_eq!(format!("{:?}", re.regexclude), r#"["^ads\\.", "\\.com$", "^Nope"]"#);
+
+		// Empty is empty.
+		let re: Wrapper = toml::from_str("regexclude = []").expect("Unable to parse regexes.");
+		assert!(re.regexclude.is_empty());
+		assert!(! re.regexclude.is_match("example.com"));
+
+		// Bad patterns should fail.
+		assert!(toml::from_str::<Wrapper>("regexclude = [ '(' ]").is_err());
+	}
+
+	#[test]
+	fn t_has_uppercase() {
+		for (raw, expected) in [
+			(r"^ads\.", false),
+			(r"^Ads\.", true),
+			(r"(?i)^Ads\.", false),
+			(r"\D+\.com$", false),
+			(r"\p{Lu}", false),
+			(r"\pL", false),
+			("[[:upper:]]", false),
+			("[A-Z]", true),
+		] {
+			assert_eq!(has_uppercase(raw), expected, "{raw}");
+		}
+	}
+
+	#[test]
+	fn t_suffixes() {
+		#[derive(Deserialize)]
+		struct Wrapper {
+			block_suffixes: Suffixes,
+			allow_suffixes: Suffixes,
+		}
+
+		let sfx: Wrapper = toml::from_str(r#"
+			block_suffixes = [ "zip", ".MOV", "uk", "co.uk" ]
+			allow_suffixes = [ "uk" ]
+		"#).expect("Unable to parse suffixes.");
+
+		assert_eq!(sfx.block_suffixes.iter().collect::<Vec<_>>(), ["co.uk", "mov", "uk", "zip"]);
+		assert!(sfx.block_suffixes.matches(&Domain::new("files.zip").unwrap()));
+		assert!(sfx.allow_suffixes.matches(&Domain::new("www.blobfolio.co.uk").unwrap()));
+		assert!(! sfx.allow_suffixes.matches(&Domain::new("files.zip").unwrap()));
+
+		// Allowing "uk" should knock out "co.uk" too.
+		let block = sfx.block_suffixes.without(&sfx.allow_suffixes);
+		assert_eq!(block.iter().collect::<Vec<_>>(), ["mov", "zip"]);
+
+		// And collapsing should remove any hosts they cover.
+		let mut list: Vec<(Domain, Provenance)> = ["blobfolio.com", "files.zip", "movies.mov"]
+			.into_iter()
+			.map(|d| (Domain::new(d).unwrap(), Provenance::default()))
+			.collect();
+		assert_eq!(collapse(&mut list, &block), 2);
+		assert_eq!(list.len(), 1);
+
+		// Bad entries should fail.
+		assert!(toml::from_str::<Wrapper>(r#"
+			block_suffixes = [ "blobfolio.com" ]
+			allow_suffixes = []
+		"#).is_err());
+	}
+
+	#[test]
+	fn t_pattern() {
+		for (raw, expected) in [
+			("Example.com", Some(Pattern::Exact(Domain::new("example.com").unwrap()))),
+			("*.example.com", Some(Pattern::Tree(Domain::new("example.com").unwrap()))),
+			("ads.*.example.com", Some(Pattern::Glob(vec!["ads".to_owned(), "*".to_owned(), "example".to_owned(), "com".to_owned()]))),
+			("@tld:example.co.uk", Some(Pattern::Tld("example.co.uk".to_owned()))),
+			("@suffix:.co.uk", Some(Pattern::Suffix("co.uk".to_owned()))),
+			("@suffix:xyz", Some(Pattern::Suffix("xyz".to_owned()))),
+			("*.com", None),
+			("ads.*.com", None),
+			("ad*.example.com", None),
+			("@tld:www.example.com", None),
+			("@suffix:example.com", None),
+			("@foo:example.com", None),
+		] {
+			assert_eq!(Pattern::from_str(raw).ok(), expected, "{raw}");
+		}
+	}
+
+	#[test]
+	fn t_protected() {
+		// Make sure all of the built-ins are valid.
+		for raw in PROTECTED {
+			assert!(Pattern::from_str(raw).is_ok(), "Invalid protected pattern: {raw}");
+		}
+
+		let protected = Patterns::protected();
+		assert!(protected.matches(&Domain::new("github.com").unwrap()));
+		assert!(protected.matches(&Domain::new("raw.githubusercontent.com").unwrap()));
+		assert!(! protected.matches(&Domain::new("mail.google.com").unwrap()));
+
+		// Extend it.
+		let mut extended = Patterns::default();
+		extended.extend(&protected);
+		extended.push(Pattern::from_str("@tld:blobfolio.com").unwrap());
+		assert!(extended.matches(&Domain::new("github.com").unwrap()));
+		assert!(extended.matches(&Domain::new("www.blobfolio.com").unwrap()));
+	}
+
+	#[test]
+	fn t_patterns() {
+		#[derive(Deserialize)]
+		struct Wrapper { exclude: Patterns }
+
+		let ex: Wrapper = toml::from_str(r#"exclude = [
+			"blobfolio.com",
+			"*.example.com",
+			"*.b.other.com",
+			"ads.*.foo.com",
+			"@tld:bar.co.uk",
+			"@suffix:zip",
+		]"#).expect("Unable to parse excludes.");
+		assert!(! ex.exclude.is_empty());
+
+		let mut list: Vec<&str> = [
+			"a.b.other.com",
+			"ads.foo.com",
+			"ads.x.foo.com",
+			"ads.x.y.foo.com",
+			"b.other.com",
+			"bar.co.uk",
+			"blobfolio.com",
+			"c.other.com",
+			"example.com",
+			"files.zip",
+			"notexample.com",
+			"other.com",
+			"www.bar.co.uk",
+			"www.blobfolio.com",
+			"www.example.com",
+			"x.y.z.example.com",
+		].into_iter().collect();
+		list.retain(|d| ! ex.exclude.matches(&Domain::new(d).unwrap()));
+		assert_eq!(
+			list,
+			["ads.foo.com", "ads.x.y.foo.com", "c.other.com", "notexample.com", "other.com", "www.blobfolio.com"],
+		);
+
+		// Bad entries should fail, all together.
+		let Err(e) = toml::from_str::<Wrapper>(r#"exclude = [ "*.com", "blobfolio.com", "-nope.com" ]"#) else {
+			panic!("Invalid excludes parsed.");
+		};
+		assert!(e.message().contains("*.com, -nope.com"), "{e}");
+	}
+
+	#[test]
+	fn t_include() {
+		#[derive(Deserialize)]
+		struct Wrapper { include: Include }
+
+		let inc: Wrapper = toml::from_str(r#"include = [
+			"WWW.Batman.com",
+			"*.robin.com",
+			"@tld:joker.com",
+			"ads.*.riddler.com",
+		]"#).expect("Unable to parse includes.");
+
+		assert_eq!(inc.include.hosts(), ["joker.com", "robin.com", "www.batman.com"]);
+		for (host, keep) in [
+			("www.robin.com", true),
+			("a.b.joker.com", true),
+			("ads.x.riddler.com", true),
+			("riddler.com", false),
+			("www.batman.com", false),
+		] {
+			assert_eq!(inc.include.keep(&Domain::new(host).unwrap()), keep, "{host}");
+		}
+
+		// Bad entries should fail, all together.
+		let Err(e) = toml::from_str::<Wrapper>(r#"include = [ "batman", "robin.com", "*.com" ]"#) else {
+			panic!("Invalid includes parsed.");
+		};
+		assert!(e.message().contains("batman, *.com"), "{e}");
+	}
+}
@@ -0,0 +1,272 @@
+/*!
+# Adbyss: Export Formats
+*/
+
+use adbyss_psl::Domain;
+use crate::{
+	AdbyssError,
+	cname::CnameTargets,
+	filter::Suffixes,
+	source::Provenance,
+};
+use std::{
+	io::{
+		self,
+		Write,
+	},
+	str::FromStr,
+};
+use utc2k::FmtUtc2k;
+
+
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+/// # Show Format.
+///
+/// This enum holds the different formats `--show` can print the shitlist in.
+///
+/// Note: for the [`Format::wildcard`] formats, redundant subdomains are
+/// collapsed before printing, and blocked suffixes get rules of their own.
+pub(super) enum Format {
+	#[default]
+	/// # Plain (One Host Per Line).
+	Plain,
+
+	/// # Adblock Filter List.
+	Adblock,
+
+	/// # CSV.
+	Csv,
+
+	/// # Dnsmasq Config.
+	Dnsmasq,
+
+	/// # JSON.
+	Json,
+
+	/// # Response Policy Zone.
+	Rpz,
+
+	/// # Unbound Config.
+	Unbound,
+}
+
+impl FromStr for Format {
+	type Err = AdbyssError;
+
+	fn from_str(src: &str) -> Result<Self, Self::Err> {
+		match src.trim() {
+			"plain" => Ok(Self::Plain),
+			"adblock" => Ok(Self::Adblock),
+			"csv" => Ok(Self::Csv),
+			"dnsmasq" => Ok(Self::Dnsmasq),
+			"json" => Ok(Self::Json),
+			"rpz" => Ok(Self::Rpz),
+			"unbound" => Ok(Self::Unbound),
+			_ => Err(AdbyssError::InvalidCli(format!("--format {src}"))),
+		}
+	}
+}
+
+impl Format {
+	/// # Wildcard?
+	///
+	/// Returns `true` if blocking a domain in this format implicitly blocks
+	/// all of its subdomains too.
+	pub(super) const fn wildcard(self) -> bool {
+		matches!(self, Self::Adblock | Self::Dnsmasq | Self::Rpz | Self::Unbound)
+	}
+
+	/// # Write Entries.
+	///
+	/// Stream the entries to the writer in the appropriate format, one at a
+	/// time.
+	///
+	/// For the wildcard formats, rules for the blocked `suffixes` are written
+	/// first; the others ignore them. The CSV and JSON formats note the
+	/// tracker target of any CNAME-sourced hosts.
+	///
+	/// ## Errors
+	///
+	/// This will bubble up any I/O errors encountered along the way.
+	pub(super) fn write<W: Write>(
+		self,
+		list: &[(Domain, Provenance)],
+		suffixes: &Suffixes,
+		cnames: &CnameTargets,
+		mut w: W,
+	) -> io::Result<()> {
+		match self {
+			Self::Plain => for (d, _) in list { writeln!(w, "{d}")?; },
+			Self::Adblock => {
+				writeln!(
+					w,
+					"[Adblock Plus]\n! Title: Adbyss\n! Last modified: {} UTC\n! Entries: {}",
+					FmtUtc2k::now(),
+					list.len() + suffixes.len(),
+				)?;
+				for d in suffixes.iter() { writeln!(w, "||{d}^")?; }
+				for (d, _) in list { writeln!(w, "||{d}^")?; }
+			},
+			Self::Csv => {
+				w.write_all(b"host,subdomain,root,suffix,tld,sources,categories,cname\n")?;
+				for (d, p) in list {
+					write!(
+						w,
+						"{d},{},{},{},{},",
+						d.subdomain().unwrap_or_default(),
+						d.root(),
+						d.suffix(),
+						d.tld(),
+					)?;
+					write_joined(&mut w, p.names(), "|", "")?;
+					w.write_all(b",")?;
+					write_joined(&mut w, p.categories(), "|", "")?;
+					writeln!(w, ",{}", cnames.get(d.as_str()).unwrap_or_default())?;
+				}
+			},
+			Self::Dnsmasq => {
+				for d in suffixes.iter() { writeln!(w, "address=/{d}/#")?; }
+				for (d, _) in list { writeln!(w, "address=/{d}/#")?; }
+			},
+			Self::Json => {
+				w.write_all(b"[")?;
+				for (k, (d, p)) in list.iter().enumerate() {
+					w.write_all(if k == 0 { b"\n" } else { b",\n" })?;
+					write!(w, r#"{{"host":"{d}","subdomain":"#)?;
+					if let Some(sub) = d.subdomain() { write!(w, r#""{sub}""#)?; }
+					else { w.write_all(b"null")?; }
+					write!(
+						w,
+						r#","root":"{}","suffix":"{}","tld":"{}","sources":["#,
+						d.root(),
+						d.suffix(),
+						d.tld(),
+					)?;
+					write_joined(&mut w, p.names(), ",", "\"")?;
+					w.write_all(b"],\"categories\":[")?;
+					write_joined(&mut w, p.categories(), ",", "\"")?;
+					w.write_all(b"],\"cname\":")?;
+					if let Some(target) = cnames.get(d.as_str()) { write!(w, r#""{target}"}}"#)?; }
+					else { w.write_all(b"null}")?; }
+				}
+				w.write_all(b"\n]\n")?;
+			},
+			Self::Rpz => {
+				writeln!(
+					w,
+					"$TTL 300\n@ IN SOA localhost. root.localhost. ({} 43200 3600 86400 300)\n  IN NS localhost.",
+					utc2k::unixtime(),
+				)?;
+				for d in suffixes.iter() { writeln!(w, "*.{d} CNAME .")?; }
+				for (d, _) in list { writeln!(w, "{d} CNAME .\n*.{d} CNAME .")?; }
+			},
+			Self::Unbound => {
+				w.write_all(b"server:\n")?;
+				for d in suffixes.iter() { writeln!(w, "local-zone: \"{d}.\" always_null")?; }
+				for (d, _) in list { writeln!(w, "local-zone: \"{d}.\" always_null")?; }
+			},
+		}
+
+		w.flush()
+	}
+}
+
+
+
+/// # Write Joined Names.
+///
+/// Write the source or category names separated by `sep`, wrapping each in
+/// `quote`.
+///
+/// Note: none of our names require escaping in either CSV or JSON.
+fn write_joined<W, I>(w: &mut W, names: I, sep: &str, quote: &str)
+-> io::Result<()>
+where W: Write, I: Iterator<Item=&'static str> {
+	for (k, name) in names.enumerate() {
+		if k != 0 { w.write_all(sep.as_bytes())?; }
+		write!(w, "{quote}{name}{quote}")?;
+	}
+	Ok(())
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		Source,
+		source::Category,
+	};
+
+	#[test]
+	fn t_format() {
+		let mut prov = Provenance::from(Source::AdAway);
+		prov |= Provenance::INCLUDE;
+		prov |= Provenance::from(Category::Ads);
+		prov |= Provenance::from(Category::Social);
+		let list = [
+			(Domain::new("blobfolio.com").unwrap(), Provenance::from(Source::Yoyo)),
+			(Domain::new("www.blobfolio.co.uk").unwrap(), prov),
+		];
+
+		let none = Suffixes::default();
+		let cnames = CnameTargets::default();
+		cnames.insert("blobfolio.com", "blobfolio.eulerian.net");
+		let mut out = Vec::new();
+		Format::Plain.write(&list, &none, &cnames, &mut out).expect("Write failed.");
+		assert_eq!(out, b"blobfolio.com\nwww.blobfolio.co.uk\n");
+
+		out.truncate(0);
+		Format::Csv.write(&list, &none, &cnames, &mut out).expect("Write failed.");
+		assert_eq!(
+			String::from_utf8(out.clone()).expect("Invalid UTF-8."),
+			"host,subdomain,root,suffix,tld,sources,categories,cname
+blobfolio.com,,blobfolio,com,blobfolio.com,Yoyo,,blobfolio.eulerian.net
+www.blobfolio.co.uk,www,blobfolio,co.uk,blobfolio.co.uk,AdAway|Include,ads|social,
+",
+		);
+
+		out.truncate(0);
+		Format::Json.write(&list, &none, &cnames, &mut out).expect("Write failed.");
+		assert_eq!(
+			String::from_utf8(out.clone()).expect("Invalid UTF-8."),
+			r#"[
+{"host":"blobfolio.com","subdomain":null,"root":"blobfolio","suffix":"com","tld":"blobfolio.com","sources":["Yoyo"],"categories":[],"cname":"blobfolio.eulerian.net"},
+{"host":"www.blobfolio.co.uk","subdomain":"www","root":"blobfolio","suffix":"co.uk","tld":"blobfolio.co.uk","sources":["AdAway","Include"],"categories":["ads","social"],"cname":null}
+]
+"#,
+		);
+
+		out.truncate(0);
+		Format::Adblock.write(&list, &none, &cnames, &mut out).expect("Write failed.");
+		let adblock = String::from_utf8(out.clone()).expect("Invalid UTF-8.");
+		assert!(adblock.starts_with("[Adblock Plus]\n! Title: Adbyss\n"));
+		assert!(adblock.ends_with("||blobfolio.com^\n||www.blobfolio.co.uk^\n"));
+
+		out.truncate(0);
+		Format::Dnsmasq.write(&list, &none, &cnames, &mut out).expect("Write failed.");
+		assert_eq!(out, b"address=/blobfolio.com/#\naddress=/www.blobfolio.co.uk/#\n");
+
+		out.truncate(0);
+		Format::Unbound.write(&list, &none, &cnames, &mut out).expect("Write failed.");
+		assert_eq!(
+			String::from_utf8(out.clone()).expect("Invalid UTF-8."),
+			"server:
+local-zone: \"blobfolio.com.\" always_null
+local-zone: \"www.blobfolio.co.uk.\" always_null
+",
+		);
+
+		out.truncate(0);
+		Format::Rpz.write(&list, &none, &cnames, &mut out).expect("Write failed.");
+		let out = String::from_utf8(out).expect("Invalid UTF-8.");
+		assert!(out.starts_with("$TTL 300\n@ IN SOA "));
+		assert!(out.ends_with("blobfolio.com CNAME .\n*.blobfolio.com CNAME .\nwww.blobfolio.co.uk CNAME .\n*.www.blobfolio.co.uk CNAME .\n"));
+
+		// Parsing.
+		assert_eq!("json".parse::<Format>().ok(), Some(Format::Json));
+		assert!("xml".parse::<Format>().is_err());
+	}
+}
@@ -0,0 +1,175 @@
+/*!
+# Adbyss: Post-Write Hooks
+*/
+
+use serde::{
+	de,
+	Deserialize,
+};
+use std::{
+	fmt,
+	path::PathBuf,
+	process::Command,
+};
+
+
+
+/// # Built-in SIGHUP Prefix.
+const SIGHUP: &str = "sighup:";
+
+
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+/// # Post-Write Hook.
+///
+/// Hooks are run after the hostfile has been changed, giving DNS caches like
+/// `nscd`, `systemd-resolved`, or `dnsmasq` a chance to catch up.
+///
+/// In the config they're just strings: `"sighup:/path/to/pidfile"` sends a
+/// `SIGHUP` to the process named by the PID file; anything else is treated as
+/// a shell command.
+pub(super) enum Hook {
+	/// # Shell Command.
+	Command(String),
+
+	/// # Send SIGHUP to the PID in This File.
+	Sighup(PathBuf),
+}
+
+impl<'de> Deserialize<'de> for Hook {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where D: de::Deserializer<'de> {
+		let raw = String::deserialize(deserializer)?;
+		let raw = raw.trim();
+		match raw.strip_prefix(SIGHUP).map(str::trim) {
+			Some("") => Err(de::Error::custom("sighup hooks require a PID file")),
+			Some(rest) => Ok(Self::Sighup(PathBuf::from(rest))),
+			None if raw.is_empty() => Err(de::Error::custom("empty post_write hook")),
+			None => Ok(Self::Command(raw.to_owned())),
+		}
+	}
+}
+
+impl fmt::Display for Hook {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Command(s) => f.write_str(s),
+			Self::Sighup(s) => write!(f, "{SIGHUP}{}", s.display()),
+		}
+	}
+}
+
+impl Hook {
+	/// # Run Hook.
+	///
+	/// Run the command or send the signal, returning a short description of
+	/// the problem if it fails.
+	///
+	/// ## Errors
+	///
+	/// Commands that cannot be spawned or exit non-zero, and signals that
+	/// cannot be sent, will return an error.
+	pub(super) fn run(&self) -> Result<(), String> {
+		match self {
+			Self::Command(cmd) => {
+				let status = Command::new("/bin/sh")
+					.args(["-c", cmd])
+					// Keep STDOUT clean for adbyss' own output.
+					.stdout(std::io::stderr())
+					.status()
+					.map_err(|e| e.to_string())?;
+
+				if status.success() { Ok(()) }
+				else if let Some(code) = status.code() { Err(format!("exit status {code}")) }
+				else { Err("terminated by signal".to_owned()) }
+			},
+			Self::Sighup(src) => {
+				use nix::{
+					sys::signal::{
+						kill,
+						Signal,
+					},
+					unistd::Pid,
+				};
+
+				let pid = std::fs::read_to_string(src)
+					.ok()
+					.and_then(|s| s.trim().parse::<i32>().ok())
+					.filter(|&p| 0 < p)
+					.ok_or_else(|| format!("invalid PID file {}", src.display()))?;
+
+				kill(Pid::from_raw(pid), Signal::SIGHUP).map_err(|e| e.to_string())
+			},
+		}
+	}
+}
+
+
+
+/// # Hook Status.
+///
+/// The result of a single hook run, for reporting purposes.
+pub(super) struct HookStatus<'a> {
+	/// # The Hook.
+	hook: &'a Hook,
+
+	/// # The Error, If Any.
+	err: Option<String>,
+}
+
+impl fmt::Display for HookStatus<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.err {
+			Some(e) => write!(f, "{} ({e})", self.hook),
+			None => write!(f, "{}", self.hook),
+		}
+	}
+}
+
+impl<'a> HookStatus<'a> {
+	/// # Run Hook.
+	pub(super) fn run(hook: &'a Hook) -> Self {
+		Self { hook, err: hook.run().err() }
+	}
+
+	/// # Failed?
+	pub(super) const fn failed(&self) -> bool { self.err.is_some() }
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_hook() {
+		#[derive(Deserialize)]
+		struct Wrapper { post_write: Vec<Hook> }
+
+		let hooks: Wrapper = toml::from_str(r#"post_write = [
+			"true",
+			"  sighup:/run/dnsmasq.pid ",
+			"exit 3",
+		]"#).expect("Unable to parse hooks.");
+
+		assert_eq!(
+			hooks.post_write,
+			[
+				Hook::Command("true".to_owned()),
+				Hook::Sighup(PathBuf::from("/run/dnsmasq.pid")),
+				Hook::Command("exit 3".to_owned()),
+			],
+		);
+
+		// Run the commands.
+		assert!(! HookStatus::run(&hooks.post_write[0]).failed());
+		let status = HookStatus::run(&hooks.post_write[2]);
+		assert!(status.failed());
+		assert_eq!(status.to_string(), "exit 3 (exit status 3)");
+
+		// Empties are no good.
+		assert!(toml::from_str::<Wrapper>(r#"post_write = [ "sighup:" ]"#).is_err());
+		assert!(toml::from_str::<Wrapper>(r#"post_write = [ " " ]"#).is_err());
+	}
+}
@@ -0,0 +1,462 @@
+/*!
+# Adbyss: Config Template
+*/
+
+use crate::{
+	AdbyssError,
+	source::{
+		Category,
+		Source,
+	},
+};
+use std::{
+	fmt::Write,
+	path::Path,
+};
+use toml::{
+	Table,
+	Value,
+};
+
+
+
+/// # Header.
+const HEADER: &str = "##
+# AdByss Configuration
+#
+# Adbyss is a DNS blocklist manager for x86-64 Linux machines.
+# https://github.com/Blobfolio/adbyss
+#
+# Changes to this file affect the default program behaviors for all users.
+#
+# Additional settings can be dropped into /etc/adbyss.d/*.toml, which are
+# merged on top of this file in lexical order. Lists are appended to, tables
+# are merged key by key, and everything else is simply overridden. Run
+# `adbyss --print-config` to see the end result.
+#
+# Unknown keys and invalid values are treated as errors. Run
+# `adbyss --check-config` after making changes to make sure everything is in
+# order.
+#
+# A fresh copy of this file — reflecting the current defaults — can be
+# generated at any time with `adbyss --init <path>`.
+#
+# Run `adbyss --help` to see additional runtime options.
+##
+";
+
+/// # Sections (Before Sources).
+///
+/// The commentary for each group of settings, along with the keys it covers.
+/// The values themselves are pulled from the defaults.
+const SECTIONS_A: [(&str, &[&str]); 3] = [
+	("##
+# Hostfile
+#
+# The absolute path to the hostfile Adbyss should write block rules to. This is
+# almost always \"/etc/hosts\", but if your system is weird, put in the correct
+# value below.
+#
+# See https://en.wikipedia.org/wiki/Hosts_(file) for general information.
+##", &["hostfile"]),
+	("##
+# Backup Hostfile
+#
+# When `true`, a backup copy of the current hostfile will be saved before
+# updating it with any shitlist changes.
+##", &["backup"]),
+	("##
+# Compact Output
+#
+# When `true`, subdomains will be grouped by their top-level domain. This
+# reduces both the line count and overall hostfile disk size (by about 15%),
+# and should result in (ever so slightly) faster DNS lookup times.
+#
+# Individual hostfile lines cannot exceed 256 characters. Large groups will
+# be split up as necessary to ensure they fit.
+##", &["compact"]),
+];
+
+/// # Sections (After Sources).
+const SECTIONS_B: [(&str, &[&str]); 11] = [
+	("##
+# CNAME-Cloaking Lists
+#
+# Trackers increasingly hide behind first-party hostnames that are really just
+# CNAMEs pointing back to them. Hostfiles can't follow CNAMEs, but they can
+# block the first-party names, so long as somebody has found them.
+#
+# Add any number of local files or http(s) URLs below, in any of these
+# formats:
+#
+# * AdGuard's cname-trackers JSON map of disguised hosts to trackers:
+#     { \"metrics.example.com\": \"example.com.ssl.sc.omtrdc.net\" }
+# * AdGuard-style rules, one per line (the trackers won't be known):
+#     ||metrics.example.com^
+# * Pairs, one per line, first-party host first, then the tracker it points
+#   to, separated by whitespace or a comma:
+#     metrics.example.com example.com.ssl.sc.omtrdc.net
+#
+# Lines starting with \"#\" or \"!\" are ignored. Where known, the trackers are
+# noted by \"adbyss check\" and the csv/json \"adbyss show\" formats.
+#
+# cname_sources = [
+#   \"/etc/adbyss/cname-trackers.json\",
+# ]
+##", &["cname_sources"]),
+	("##
+# WWW Expansion
+#
+# Blocklists frequently include \"www.example.com\" without \"example.com\", or
+# vice versa. This controls whether the counterparts are added too, for the
+# sources, includes, and (exact) excludes alike:
+#
+#   \"none\"    Leave hosts as-is.
+#   \"strip\"   Add \"example.com\" for each \"www.example.com\".
+#   \"both\"    Also add \"www.example.com\" for each \"example.com\".
+##", &["www_expansion"]),
+	("##
+# Exclude Hosts
+#
+# Depending on your life, you may need to access a host Adbyss would normally
+# block.
+#
+# Add any such hosts below to have them excluded from the blocklists, like:
+#
+# exclude = [
+#  \"example1.com\",
+#  \"example2.com\",
+# ]
+#
+# Simple patterns are supported too:
+#
+#   \"*.example.com\"       example.com and all of its subdomains.
+#   \"ads.*.example.com\"   ads.<anything>.example.com (one label per \"*\").
+#   \"@tld:example.com\"    Any host whose TLD is example.com.
+#   \"@suffix:xyz\"         Any host under the public suffix xyz.
+#
+# exclude = [
+#  \"*.example3.com\",
+#  \"@suffix:zip\",
+# ]
+##", &["exclude"]),
+	("##
+# Exclude Hosts (Regex)
+#
+# For more complicated exclusion needs, you can provide any number of regular
+# expression patterns.
+#
+# Add any such patterns below. Any host matching any pattern will be excluded
+# from the blocklist.
+#
+# Note: hosts are automatically cast to lowercase; your patterns should only
+# target [a-z] ranges.
+#
+# See https://docs.rs/regex/1.4.1/regex/index.html#syntax for regular
+# expression syntax information.
+#
+# In practice this would look something like:
+#
+# regexclude = [ '(.+\\.)*example\\.com$' ]
+##", &["regexclude"]),
+	("##
+# Include Hosts (Regex)
+#
+# Any host matching any of these patterns will be kept in the blocklist even
+# if one of the exclusion rules above would otherwise remove it, like:
+#
+# regexinclude = [ '^ads\\.(.+\\.)*example\\.com$' ]
+#
+# After each run, Adbyss reports how many hosts each regexclude/regexinclude
+# pattern matched, and warns about any that matched nothing, or that contain
+# uppercase characters (which can never match).
+##", &["regexinclude"]),
+	("##
+# Custom Blocks
+#
+# If you have additional enemies, add the host(s) below, like:
+#
+# include = [
+#   \"example1.com\",
+#   \"example2.com\",
+# ]
+#
+# The exclude patterns above work here too, but since they can't conjure hosts
+# out of thin air, they apply to the hosts found in the sources instead: any
+# match will be kept even if an exclude rule would otherwise remove it. (The
+# parent of a \"*.example.com\" or \"@tld:example.com\" pattern is also added.)
+#
+# You can also save any number of custom entries directly to your hostfile.
+# Adbyss will preserve anything outside the ADBYSS block when writing
+# blocklist updates.
+##", &["include"]),
+	("##
+# Protected Hosts
+#
+# A poisoned or overzealous source could end up blocking something essential,
+# like github.com or your OS' update mirrors. To guard against that, Adbyss
+# ships with a short list of protected hosts that will be dropped from the
+# blocklist — with a loud warning — should they ever turn up.
+#
+# Set protect_defaults to false to disable the built-in list, and/or add your
+# own (e.g. SSO) hosts to protect, using any of the exclude pattern forms.
+#
+# If protect_strict is true, Adbyss will abort instead, leaving the hostfile
+# untouched.
+#
+# Hosts you explicitly include are never affected.
+##", &["protect_defaults", "protect", "protect_strict"]),
+	("##
+# Public Suffixes
+#
+# Some suffixes are so thoroughly abused you might want to block every host
+# registered under them, while others you might never want blocked at all.
+#
+# Hosts under an \"allowed\" suffix are removed from the blocklist (unless
+# explicitly included).
+#
+# Blocking a suffix outright, however, can't be expressed in a hostfile, so
+# block_suffixes ONLY apply to the wildcard \"adbyss show --format\" outputs
+# (adblock, dnsmasq, rpz, unbound). They have no effect on the hostfile —
+# regular updates, --stdout, check, and diff — nor the plain, csv, and json
+# formats; Adbyss will warn about them there.
+#
+# block_suffixes = [ \"zip\", \"mov\" ]
+# allow_suffixes = [ \"gov\" ]
+##", &["block_suffixes", "allow_suffixes"]),
+	("##
+# Rejected Entries
+#
+# Upstream lists sometimes carry entries that aren't valid hosts — bad labels,
+# unknown or bare public suffixes, etc. — or are too long for a hostfile.
+# Adbyss skips these, summarizing the rejects for each source after each run.
+#
+# To save the full list somewhere — handy for upstream bug reports — set the
+# path below, like:
+#
+# rejected_file = \"/var/log/adbyss-rejected.tsv\"
+##", &[]),
+	("##
+# Run Report
+#
+# For automation, Adbyss can save a JSON summary of each update — the status,
+# cache use, size, and entry count of each source list, the parsed, rejected,
+# excluded, and unique totals, the files written, timings, and the error, if
+# any — to the path below, like:
+#
+# report_path = \"/var/lib/adbyss/report.json\"
+#
+# (Use --report json to print it to STDOUT instead.)
+##", &[]),
+	("##
+# Post-Write Hooks
+#
+# DNS caches like nscd, systemd-resolved, and dnsmasq may keep serving stale
+# answers until their entries expire. If you use one, you can have Adbyss
+# nudge it whenever the hostfile actually changes.
+#
+# Each entry is either a shell command, or \"sighup:/path/to/pidfile\" to send
+# a SIGHUP to the process named by the PID file. Hooks run in order; if any
+# of them fail, Adbyss will exit with an error.
+#
+# post_write = [
+#   \"resolvectl flush-caches\",
+#   \"sighup:/run/dnsmasq/dnsmasq.pid\",
+# ]
+##", &["post_write"]),
+];
+
+/// # Footer.
+const FOOTER: &str = "##
+# Profiles
+#
+# Any of the settings above can be tweaked for a named profile, selected at
+# runtime with `--profile <name>` or the ADBYSS_PROFILE environment variable.
+# The profile is layered on top of the base settings: lists are appended to,
+# and everything else is overridden.
+#
+# [profile.kiosk]
+# source_adaway = false
+# exclude = [ \"intranet.example.com\" ]
+#
+# [profile.kiosk.categories]
+# porn = true
+# social = true
+##
+";
+
+/// # Categories Key.
+const CATEGORIES: &str = "categories";
+
+
+
+/// # Render Config.
+///
+/// Return a fully-commented config file reflecting the default settings and
+/// every available source and category.
+///
+/// ## Errors
+///
+/// This will return an error if the defaults are missing a key, which should
+/// never happen.
+pub(super) fn render() -> Result<String, AdbyssError> {
+	let defaults = crate::config::defaults()?;
+	let all = Category::ALL.iter().fold(0, |acc, c| acc | *c as u8);
+
+	let mut out = String::from(HEADER);
+	for section in SECTIONS_A { render_section(&mut out, &defaults, section)?; }
+
+	// Sources.
+	out.push_str("\n\n##
+# Block Sources
+#
+# If for some reason you would like to exclude one of Adbyss' sources, set the
+# value to false. The lists each pulls from — depending on the categories
+# enabled below — are as follows:
+#\n");
+	let mut keys = Vec::with_capacity(Source::ALL.len());
+	for src in Source::ALL {
+		let _res = writeln!(&mut out, "#   source_{}", src.key());
+		for list in src.lists(all) {
+			let categories = list.provenance().categories().collect::<Vec<_>>().join(", ");
+			let url = if list.url().is_empty() { "(built-in)" } else { list.url() };
+			let _res = writeln!(&mut out, "#     {url} [{categories}]");
+		}
+		keys.push(format!("source_{}", src.key()));
+	}
+	out.push_str("##\n\n");
+	for key in keys { render_value(&mut out, &defaults, &key)?; }
+
+	for section in SECTIONS_B { render_section(&mut out, &defaults, section)?; }
+
+	// Categories.
+	out.push_str("\n\n##
+# Categories
+#
+# Some sources offer lists for more than just ads and malware. Enable any of
+# the categories below to have them blocked too, or disable ads/malware to
+# skip the lists dedicated to those. The sources covering each are:
+#\n");
+	for cat in Category::ALL {
+		let sources = Source::ALL.into_iter()
+			.filter(|s| s.lists(cat as u8).next().is_some())
+			.map(Source::as_str)
+			.collect::<Vec<_>>()
+			.join(", ");
+		let _res = writeln!(&mut out, "#   {:<10}{sources}", cat.as_str());
+	}
+	out.push_str("#
+# (Note: table sections like this one must come after all the plain
+# settings.)
+##
+
+[categories]\n");
+	let Some(Value::Table(categories)) = defaults.get(CATEGORIES) else {
+		return Err(AdbyssError::Parse(CATEGORIES.to_owned()));
+	};
+	for cat in Category::ALL { render_value(&mut out, categories, cat.as_str())?; }
+
+	out.push_str("\n\n");
+	out.push_str(FOOTER);
+	Ok(out)
+}
+
+/// # Write Config.
+///
+/// Render the config and save it to `dst`, or print it if `dst` is `-`.
+///
+/// ## Errors
+///
+/// This will return an error if the file already exists and `force` is
+/// `false`, or if it cannot be written.
+pub(super) fn write(dst: &Path, force: bool) -> Result<(), AdbyssError> {
+	use std::io::Write;
+
+	let out = render()?;
+	if dst == Path::new("-") {
+		let mut handle = std::io::stdout().lock();
+		let _res = handle.write_all(out.as_bytes()).and_then(|()| handle.flush());
+		return Ok(());
+	}
+
+	if ! force && matches!(std::fs::exists(dst), Ok(true)) {
+		return Err(AdbyssError::Exists(dst.to_string_lossy().into_owned()));
+	}
+
+	crate::write::write_to_file(dst, out.as_bytes())
+}
+
+/// # Render Section.
+fn render_section(out: &mut String, defaults: &Table, (comment, keys): (&str, &[&str]))
+-> Result<(), AdbyssError> {
+	out.push_str("\n\n");
+	out.push_str(comment);
+	out.push('\n');
+	if ! keys.is_empty() { out.push('\n'); }
+	for key in keys { render_value(out, defaults, key)?; }
+	Ok(())
+}
+
+/// # Render Value.
+fn render_value(out: &mut String, table: &Table, key: &str) -> Result<(), AdbyssError> {
+	let value = table.get(key).ok_or_else(|| AdbyssError::Parse(key.to_owned()))?;
+	let _res = writeln!(out, "{key} = {value}");
+	Ok(())
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		Config,
+		Settings,
+	};
+	use std::collections::BTreeSet;
+
+	#[test]
+	fn t_keys() {
+		// Every default should be accounted for.
+		let defaults: BTreeSet<String> = crate::config::defaults()
+			.expect("Defaults failed to parse.")
+			.keys()
+			.cloned()
+			.collect();
+		let mut keys: BTreeSet<String> = SECTIONS_A.iter()
+			.chain(SECTIONS_B.iter())
+			.flat_map(|(_, keys)| keys.iter().map(|k| (*k).to_owned()))
+			.collect();
+		keys.extend(Source::ALL.iter().map(|s| format!("source_{}", s.key())));
+		keys.insert(CATEGORIES.to_owned());
+		assert_eq!(defaults, keys);
+	}
+
+	#[test]
+	fn t_render() {
+		let out = render().expect("Render failed.");
+
+		// The packaged config should be a straight copy.
+		assert_eq!(
+			include_str!("../skel/adbyss.toml"),
+			out,
+			"skel/adbyss.toml is out of date; regenerate it with --init.",
+		);
+
+		// It should round-trip.
+		let dst = std::env::temp_dir().join(format!("adbyss-t-init-{}.toml", std::process::id()));
+		let first = write(&dst, true);
+		let second = write(&dst, false);
+		let config = Config::load(Some(&dst), None);
+		let _res = std::fs::remove_file(&dst);
+
+		first.expect("Write failed.");
+		assert!(matches!(second, Err(AdbyssError::Exists(_))));
+		let config = config.expect("Config failed to load.");
+		let settings = Settings::from_config(&config).expect("Settings failed to load.");
+		assert_eq!(
+			format!("{settings:?}"),
+			format!("{:?}", Settings::default()),
+		);
+	}
+}
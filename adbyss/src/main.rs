@@ -52,27 +52,63 @@
 )]
 
 #![expect(clippy::redundant_pub_crate, reason = "Unresolvable.")]
+#![cfg_attr(
+	test,
+	expect(clippy::items_after_test_module, reason = "The generated flags.rs has its own tests."),
+)]
 
 
 
+mod cname;
+mod cmd;
+mod config;
+mod edit;
 mod err;
+mod filter;
+mod format;
+mod hook;
+mod init;
+mod pause;
+mod reject;
+mod report;
 mod settings;
 mod source;
 mod write;
 
+use cmd::Command;
+use config::{
+	Config,
+	Overrides,
+};
+use edit::ListEdit;
 use err::AdbyssError;
+use format::Format;
+use hook::HookStatus;
+use report::RunStats;
 use settings::Settings;
 use source::Source;
 use write::Shitlist;
 
-use fyi_msg::Msg;
+use adbyss_psl::Domain;
+use fyi_msg::{
+	fyi_ansi::dim,
+	Msg,
+};
 use dactyl::NiceU64;
+use utc2k::FmtUtc2k;
 use std::{
-	io::Write,
-	process::{
-		Command,
-		ExitCode,
+	ffi::OsString,
+	io::{
+		BufWriter,
+		Write,
+	},
+	os::unix::process::ExitStatusExt,
+	path::{
+		Path,
+		PathBuf,
 	},
+	process::ExitCode,
+	time::Instant,
 };
 
 
@@ -89,17 +125,300 @@ include!(concat!(env!("OUT_DIR"), "/flags.rs"));
 const MAX_LINE: usize = 245;
 
 
+
+/// # Parsed CLI.
+struct Cli {
+	/// # Command.
+	command: Command,
+
+	/// # Config Path.
+	config: Option<String>,
+
+	/// # Output Format.
+	format: Format,
+
+	/// # Config Profile.
+	profile: Option<String>,
+
+	/// # Print a JSON Run Report?
+	report: bool,
+
+	/// # Deprecated Flags Used.
+	///
+	/// These are paired with the commands that replaced them.
+	deprecated: Vec<(&'static str, &'static str)>,
+
+	/// # Setting Overrides.
+	overrides: Overrides,
+
+	/// # Flags.
+	flags: Flags,
+}
+
+impl Cli {
+	/// # Parse.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if any of the arguments are invalid or
+	/// conflict, or for `--help`/`--version` (which aren't really errors).
+	#[expect(clippy::too_many_lines, reason = "There are a lot of arguments.")]
+	fn parse() -> Result<Self, AdbyssError> {
+		// Set up the parser.
+		argyle::argue! {
+			Backup            "--backup",
+			CheckConfig       "--check-config",
+			Compact           "--compact",
+			Disable           "--disable",
+			Force             "--force",
+			Help         "-h" "--help",
+			Init              "--init",
+			NoBackup          "--no-backup",
+			NoCompact         "--no-compact",
+			NoEscalate        "--no-escalate",
+			NoProtectDefaults "--no-protect-defaults",
+			NoProtectStrict   "--no-protect-strict",
+			PrintConfig       "--print-config",
+			ProtectDefaults   "--protect-defaults",
+			ProtectStrict     "--protect-strict",
+			Quiet        "-q" "--quiet",
+			Resume            "--resume",
+			Show              "--show",
+			Status            "--status",
+			Stdout            "--stdout",
+			Systemd           "--systemd",
+			Update            "--update",
+			Version      "-V" "--version",
+			Yes          "-y" "--yes",
+			@options
+			AllowSuffix       "--allow-suffix",
+			BlockSuffix       "--block-suffix",
+			Category          "--category",
+			Check             "--check",
+			CnameSource       "--cname-source",
+			Config       "-c" "--config",
+			Exclude           "--exclude",
+			Format            "--format",
+			Hostfile          "--hostfile",
+			Include           "--include",
+			Pause             "--pause",
+			PostWrite         "--post-write",
+			Profile           "--profile",
+			Protect           "--protect",
+			Regexclude        "--regexclude",
+			Regexinclude      "--regexinclude",
+			RejectedFile      "--rejected-file",
+			Report            "--report",
+			ReportPath        "--report-path",
+			Source            "--source",
+			WwwExpansion      "--www-expansion",
+		}
+
+		// See what we've got!
+		let mut command = None;
+		let mut format = None;
+		let mut out = Self {
+			command: Command::Update,
+			config: None,
+			format: Format::Plain,
+			profile: None,
+			report: false,
+			deprecated: Vec::new(),
+			overrides: Overrides::default(),
+			flags: Flags::None,
+		};
+		for arg in Argument::args_os() {
+			match arg {
+				Argument::Force =>   { out.flags.set(Flags::Force); },
+				Argument::Help =>    { out.flags.set(Flags::Help); },
+				Argument::NoEscalate => { out.flags.set(Flags::NoEscalate); },
+				Argument::Quiet =>   { out.flags.set(Flags::Quiet); },
+				Argument::Stdout =>  { out.flags.set(Flags::Stdout); },
+				Argument::Systemd => { out.flags.set(Flags::Systemd); },
+				Argument::Update =>  { out.flags.set(Flags::Update); },
+				Argument::Yes =>     { out.flags.set(Flags::Yes); },
+
+				Argument::Version => return Err(AdbyssError::PrintVersion),
+
+				// Commands disguised as flags.
+				Argument::CheckConfig => { Command::set(&mut command, Command::CheckConfig)?; },
+				Argument::Init =>        { Command::set(&mut command, Command::Init(None))?; },
+				Argument::PrintConfig => { Command::set(&mut command, Command::PrintConfig)?; },
+				Argument::Resume =>      { Command::set(&mut command, Command::Resume)?; },
+				Argument::Pause(s) => {
+					let secs = pause::parse_duration(&s)
+						.ok_or_else(|| AdbyssError::InvalidCli(format!("--pause {s}")))?;
+					Command::set(&mut command, Command::Pause(secs))?;
+				},
+
+				// Deprecated command flags.
+				Argument::Check(s) => {
+					let dom = Domain::new(&s).ok_or_else(|| AdbyssError::InvalidCli(format!("--check {s}")))?;
+					Command::set(&mut command, Command::Check(Some(dom)))?;
+					out.deprecated.push(("--check", "check"));
+				},
+				Argument::Disable => {
+					Command::set(&mut command, Command::Disable)?;
+					out.deprecated.push(("--disable", "disable"));
+				},
+				Argument::Show => {
+					Command::set(&mut command, Command::Show)?;
+					out.deprecated.push(("--show", "show"));
+				},
+				Argument::Status => {
+					Command::set(&mut command, Command::Status)?;
+					out.deprecated.push(("--status", "status"));
+				},
+
+				Argument::Config(s) =>  { out.config.replace(s); },
+				Argument::Format(s) =>  { format.replace(s.parse::<Format>()?); },
+				Argument::Profile(s) => { out.profile.replace(s); },
+				Argument::Report(s) =>
+					if s.trim().eq_ignore_ascii_case("json") {
+						// The report gets STDOUT all to itself.
+						out.report = true;
+						out.flags.set(Flags::Quiet);
+					}
+					else { return Err(AdbyssError::InvalidCli(format!("--report {s}"))); },
+
+				// Setting overrides.
+				Argument::Backup =>            { out.overrides.set("--backup", "backup", true); },
+				Argument::Compact =>           { out.overrides.set("--compact", "compact", true); },
+				Argument::NoBackup =>          { out.overrides.set("--no-backup", "backup", false); },
+				Argument::NoCompact =>         { out.overrides.set("--no-compact", "compact", false); },
+				Argument::NoProtectDefaults => { out.overrides.set("--no-protect-defaults", "protect_defaults", false); },
+				Argument::NoProtectStrict =>   { out.overrides.set("--no-protect-strict", "protect_strict", false); },
+				Argument::ProtectDefaults =>   { out.overrides.set("--protect-defaults", "protect_defaults", true); },
+				Argument::ProtectStrict =>     { out.overrides.set("--protect-strict", "protect_strict", true); },
+
+				Argument::AllowSuffix(s) =>  { out.overrides.push("--allow-suffix", "allow_suffixes", s); },
+				Argument::BlockSuffix(s) =>  { out.overrides.push("--block-suffix", "block_suffixes", s); },
+				Argument::Category(s) =>     { out.overrides.toggle_categories(&s)?; },
+				Argument::CnameSource(s) =>  { out.overrides.push("--cname-source", "cname_sources", s); },
+				Argument::Exclude(s) =>      { out.overrides.push("--exclude", "exclude", s); },
+				Argument::Hostfile(s) =>     { out.overrides.set("--hostfile", "hostfile", s); },
+				Argument::Include(s) =>      { out.overrides.push("--include", "include", s); },
+				Argument::PostWrite(s) =>    { out.overrides.push("--post-write", "post_write", s); },
+				Argument::Protect(s) =>      { out.overrides.push("--protect", "protect", s); },
+				Argument::Regexclude(s) =>   { out.overrides.push("--regexclude", "regexclude", s); },
+				Argument::Regexinclude(s) => { out.overrides.push("--regexinclude", "regexinclude", s); },
+				Argument::RejectedFile(s) => { out.overrides.set("--rejected-file", "rejected_file", s); },
+				Argument::ReportPath(s) =>   { out.overrides.set("--report-path", "report_path", s); },
+				Argument::Source(s) =>       { out.overrides.toggle_sources(&s)?; },
+				Argument::WwwExpansion(s) => { out.overrides.set("--www-expansion", "www_expansion", s); },
+
+				// Subcommands and their arguments.
+				Argument::Other(s) => {
+					let s = match &mut command {
+						Some(cmd) => cmd.push_arg(s)?,
+						None => Some(s),
+					};
+					if let Some(s) = s {
+						match Command::from_name(&s) {
+							Some(cmd) => { Command::set(&mut command, cmd)?; },
+							None if s == "help" && command.is_none() && ! out.flags.contains(Flags::Help) => {
+								out.flags.set(Flags::Help);
+							},
+							_ => return Err(AdbyssError::InvalidCli(s)),
+						}
+					}
+				},
+				Argument::OtherOs(s) => return Err(AdbyssError::InvalidCli(s.to_string_lossy().into_owned())),
+			}
+		}
+
+		// Help?
+		if out.flags.contains(Flags::Help) {
+			return Err(AdbyssError::PrintHelp(command.as_ref().and_then(Command::help)));
+		}
+
+		// A --format implies show.
+		if let Some(format) = format {
+			if command.is_none() { command.replace(Command::Show); }
+			if command != Some(Command::Show) {
+				return Err(conflict(command.as_ref(), "--format"));
+			}
+			out.format = format;
+		}
+
+		// Make sure everything else lines up.
+		let command = command.unwrap_or(Command::Update);
+		command.finish()?;
+		if out.flags.contains(Flags::Force) && ! matches!(command, Command::Init(_)) {
+			return Err(conflict(Some(&command), "--force"));
+		}
+		if out.flags.contains(Flags::Stdout) && ! matches!(command, Command::Update) {
+			return Err(conflict(Some(&command), "--stdout"));
+		}
+		if out.flags.contains(Flags::Update) && ! matches!(command, Command::Edit(..)) {
+			return Err(conflict(Some(&command), "--update"));
+		}
+		if out.report {
+			if out.flags.contains(Flags::Stdout) {
+				return Err(AdbyssError::Conflict("--stdout, --report".to_owned()));
+			}
+			if ! command.reportable(out.flags.contains(Flags::Update)) {
+				return Err(conflict(Some(&command), "--report"));
+			}
+		}
+
+		out.command = command;
+		Ok(out)
+	}
+
+	/// # Load Config.
+	///
+	/// Fill in the default config path and profile — if they exist and weren't
+	/// set explicitly — then load and merge everything, including any
+	/// overrides.
+	///
+	/// ## Errors
+	///
+	/// This will bubble up any errors encountered while loading the config.
+	fn load_config(&mut self) -> Result<Config, AdbyssError> {
+		if self.config.is_none() && matches!(std::fs::exists(Settings::DEFAULT_CONFIG), Ok(true)) {
+			self.config.replace(Settings::DEFAULT_CONFIG.to_owned());
+		}
+		if self.profile.is_none() {
+			self.profile = std::env::var(config::PROFILE_ENV).ok().filter(|p| ! p.trim().is_empty());
+		}
+		let mut out = Config::load(self.config.as_deref().map(Path::new), self.profile.as_deref())?;
+		out.apply_overrides(&self.overrides)?;
+		Ok(out)
+	}
+
+	/// # Resume Arguments.
+	///
+	/// Return the arguments needed to reproduce the current config for a
+	/// future run.
+	fn resume_args(&self) -> Vec<String> {
+		let mut out = Vec::new();
+		if let Some(config) = &self.config {
+			out.push("-c".to_owned());
+			out.push(config.clone());
+		}
+		if let Some(profile) = &self.profile {
+			out.push("--profile".to_owned());
+			out.push(profile.clone());
+		}
+		out.extend_from_slice(self.overrides.args());
+		out
+	}
+}
+
+
+
 /// Main.
 fn main() -> ExitCode {
 	match main__() {
 		Ok(()) => ExitCode::SUCCESS,
-		Err(e @ (AdbyssError::PrintHelp | AdbyssError::PrintVersion)) => {
+		Err(e @ (AdbyssError::PrintHelp(_) | AdbyssError::PrintVersion)) => {
 			println!("{e}");
 			ExitCode::SUCCESS
 		},
 		Err(e) => {
 			Msg::error(e.to_string()).eprint();
-			ExitCode::FAILURE
+			ExitCode::from(e.exit_code())
 		},
 	}
 }
@@ -107,95 +426,292 @@ fn main() -> ExitCode {
 #[inline]
 /// Actual Main.
 fn main__() -> Result<(), AdbyssError> {
-	// We need root!
-	require_root()?;
-
-	// Set up the parser.
-	argyle::argue! {
-		Disable      "--disable",
-		Help    "-h" "--help",
-		Quiet   "-q" "--quiet",
-		Show         "--show",
-		Stdout       "--stdout",
-		Systemd      "--systemd",
-		Version "-V" "--version",
-		Yes     "-y" "--yes",
-		@options
-		Config  "-c" "--config",
-	}
-
-	// See what we've got!
-	let mut config = None;
-	let mut flags = Flags::None;
-	for arg in Argument::args_os() {
-		match arg {
-			Argument::Disable => { flags.set(Flags::Disable); },
-			Argument::Quiet =>   { flags.set(Flags::Quiet); },
-			Argument::Show =>    { flags.set(Flags::Show); },
-			Argument::Stdout =>  { flags.set(Flags::Stdout); },
-			Argument::Systemd => { flags.set(Flags::Systemd); },
-			Argument::Yes =>     { flags.set(Flags::Yes); },
-
-			Argument::Help => return Err(AdbyssError::PrintHelp),
-			Argument::Version => return Err(AdbyssError::PrintVersion),
-
-			Argument::Config(s) => { config.replace(s); },
-
-			// Nothing else is expected.
-			Argument::Other(s) => return Err(AdbyssError::InvalidCli(s)),
-			Argument::OtherOs(s) => return Err(AdbyssError::InvalidCli(s.to_string_lossy().into_owned())),
+	// Note the time, for the run report.
+	let started = utc2k::unixtime();
+	let now = Instant::now();
+
+	// Parse the CLI.
+	let mut cli = Cli::parse()?;
+	let flags = cli.flags;
+
+	// Write a fresh config? (This doesn't depend on the current one.)
+	if let Command::Init(dst) = &cli.command {
+		let dst = dst.as_deref().unwrap_or(Settings::DEFAULT_CONFIG);
+		if dst != "-" { require_root([dst], flags)?; }
+		init::write(Path::new(dst), flags.contains(Flags::Force))?;
+		if dst != "-" { Msg::success(format!("Wrote {dst}.")).eprint(); }
+		return Ok(());
+	}
+
+	// Everything else might need reporting on, failures included, so hold
+	// onto the settings and result.
+	let mut settings = None;
+	let res = load_and_run(&mut cli, &mut settings, flags);
+	if
+		cli.command.reportable(flags.contains(Flags::Update)) &&
+		(cli.report || settings.as_ref().is_some_and(|s| s.report_path().is_some()))
+	{
+		// If the settings never made it, neither did any stats.
+		let fallback = RunStats::default();
+		let stats = settings.as_ref().map_or(&fallback, Settings::stats);
+		let report = stats.render(cli.command.name(), started, now.elapsed(), &res);
+		if cli.report {
+			let mut handle = std::io::stdout().lock();
+			let _res = handle.write_all(report.as_bytes()).and_then(|()| handle.flush());
+		}
+		if let Some(dst) = settings.as_ref().and_then(Settings::report_path) {
+			// The run's own error, if any, takes precedence.
+			let saved = write::write_to_file(dst, report.as_bytes());
+			return res.and(saved);
 		}
 	}
 
+	res
+}
+
+/// # Load and Run.
+///
+/// Load the settings — stashing them in `dst` for the run report — and do
+/// whatever the command calls for.
+///
+/// ## Errors
+///
+/// This will bubble up any errors encountered along the way.
+fn load_and_run(cli: &mut Cli, dst: &mut Option<Settings>, flags: Flags)
+-> Result<(), AdbyssError> {
+	// Edit the include/exclude lists, then apply the changes (or not).
+	if matches!(cli.command, Command::Edit(..)) {
+		let main = PathBuf::from(cli.config.as_deref().unwrap_or(Settings::DEFAULT_CONFIG));
+
+		// Make sure we can write everything the update will write too, before
+		// editing anything, so an escalated restart doesn't redo the edit.
+		if flags.contains(Flags::Update) {
+			let settings = Settings::from_config(&cli.load_config()?)?;
+			let (_, rejected) = settings.rejected();
+			require_root(
+				std::iter::once(main.as_path())
+					.chain(settings.hostfile_paths().iter().map(PathBuf::as_path))
+					.chain(rejected)
+					.chain(settings.report_path()),
+				flags,
+			)?;
+		}
+		else { require_root([&main], flags)?; }
+
+		if let Command::Edit(edit, hosts) = &cli.command {
+			edit_lists(cli.config.as_deref(), *edit, hosts, flags)?;
+		}
+		if ! flags.contains(Flags::Update) { return Ok(()); }
+	}
+
 	// Build the proper settings.
-	if config.is_none() && matches!(std::fs::exists(Settings::DEFAULT_CONFIG), Ok(true)) {
-		config.replace(Settings::DEFAULT_CONFIG.to_owned());
+	let merged = cli.load_config()?;
+	let settings = dst.insert(Settings::from_config(&merged)?);
+	let cli: &Cli = cli;
+
+	// Make sure we can write whatever needs writing.
+	let (_, rejected) = settings.rejected();
+	match &cli.command {
+		Command::Check(_) | Command::CheckConfig | Command::Diff |
+		Command::PrintConfig | Command::Status => {},
+		Command::Show => require_root(rejected, flags)?,
+		_ if flags.contains(Flags::Stdout) => require_root(rejected.into_iter().chain(settings.report_path()), flags)?,
+		_ => require_root(
+			settings.hostfile_paths().iter()
+				.map(PathBuf::as_path)
+				.chain(rejected)
+				.chain(settings.report_path()),
+			flags,
+		)?,
+	}
+
+	// Let the user know about any deprecated flags.
+	if ! flags.contains(Flags::Quiet) {
+		for (old, new) in &cli.deprecated {
+			Msg::warning(format!("{old} is deprecated; use \"adbyss {new}\" instead.")).eprint();
+		}
 	}
-	let settings =
-		if let Some(config) = config { Settings::from_file(config)? }
-		else { Settings::default() };
 
-	// Remove everything?
-	if flags.contains(Flags::Disable) {
-		return settings.unwrite(flags.contains(Flags::Yes));
+	match &cli.command {
+		// Just check the config. (Any problems will have already bubbled up.)
+		Command::CheckConfig => {
+			check_config_ok(&merged);
+			return Ok(());
+		},
+
+		// Just print the config.
+		Command::PrintConfig => {
+			let mut handle = std::io::stdout().lock();
+			let _res = write!(handle, "{merged}").and_then(|()| handle.flush());
+			return Ok(());
+		},
+
+		// Remove everything?
+		Command::Disable => {
+			let hooks = settings.unwrite(flags.contains(Flags::Yes))?;
+			return summarize_hooks(&hooks, flags);
+		},
+
+		// Put the backup back?
+		Command::Restore => return restore_adbyss(settings, flags),
+
+		// Just report the status.
+		Command::Status => return print_status(settings),
+
+		// Pause or resume?
+		Command::Pause(secs) => return pause_adbyss(settings, *secs, &cli.resume_args(), flags),
+		Command::Resume => return resume_adbyss(settings, flags),
+
+		// Honor any pending pause before updating.
+		Command::Edit(..) | Command::Update if
+			! flags.contains(Flags::Stdout) &&
+			still_paused(settings, flags)? => return Ok(()),
+
+		_ => {},
 	}
 
+	run(cli, settings, flags)
+}
+
+/// # Run.
+///
+/// Do whatever it is that needs doing once the settings are sorted: check,
+/// diff, show, or build/write the shitlist.
+///
+/// ## Errors
+///
+/// This will bubble up any errors encountered along the way.
+fn run(cli: &Cli, settings: &Settings, flags: Flags) -> Result<(), AdbyssError> {
 	// Make sure we're online if any sources other than our own are enabled.
 	if settings.needs_internet() { check_internet()?; }
 
-	// Just print the domains.
-	if flags.contains(Flags::Show) {
-		let shitlist = settings.shitlist()?.into_vec();
-		if shitlist.is_empty() { return Err(AdbyssError::NoShitlist); }
+	// Blocked suffixes can only be expressed by the wildcard formats.
+	let wildcard = matches!(cli.command, Command::Show) && cli.format.wildcard();
+	if ! (wildcard || flags.contains(Flags::Quiet) || settings.block_suffixes().is_empty()) {
+		let msg = "block_suffixes only apply to the adblock, dnsmasq, rpz, and unbound show formats; ignoring them.";
+		if flags.contains(Flags::Systemd) { eprintln!("{msg}"); }
+		else { Msg::warning(msg).eprint(); }
+	}
+
+	match &cli.command {
+		// Look up a single host.
+		Command::Check(Some(dom)) => check_host(settings, dom, flags),
+
+		// Preview the changes.
+		Command::Diff => diff_adbyss(settings, flags),
+
+		// Just print the domains.
+		Command::Show => show_adbyss(settings, cli.format, flags),
+
+		// Build the shitlist, but print it instead of saving it.
+		_ if flags.contains(Flags::Stdout) => {
+			let (out, _) = settings.build()?;
+			let mut handle = std::io::stdout().lock();
+			let _res = handle.write_all(out.as_bytes()).and_then(|()| handle.flush());
+			drop(handle);
+			settings.stats().wrote("stdout", Path::new("-"));
+			report_run(settings, flags)
+		},
 
-		let mut handle = std::io::stdout().lock();
-		for v in shitlist { let _res = writeln!(&mut handle, "{v}"); }
-		let _res = handle.flush();
+		// Actually write the changes to the host file!
+		_ => write_adbyss(settings, flags),
 	}
-	// Build the shitlist, but print it instead of saving it.
-	else if flags.contains(Flags::Stdout) {
-		let (out, _) = settings.build()?;
-		let mut handle = std::io::stdout().lock();
-		let _res = handle.write_all(out.as_bytes()).and_then(|()| handle.flush());
+}
+
+/// # Conflict Error.
+///
+/// Return an error noting the command and the argument that doesn't belong
+/// with it.
+fn conflict(command: Option<&Command>, arg: &str) -> AdbyssError {
+	AdbyssError::Conflict(format!("{}, {arg}", command.map_or("update", Command::name)))
+}
+
+/// # Config OK.
+///
+/// Report that the config file(s) checked out.
+fn check_config_ok(config: &Config) {
+	let files = config.files();
+	if files.is_empty() {
+		Msg::success("No config files found; the defaults are fine.").eprint();
 	}
-	// Actually write the changes to the host file!
 	else {
-		let len = settings.write(flags.contains(Flags::Yes))?;
+		Msg::success(format!("The configuration is valid: {}", files.join(", "))).eprint();
+	}
+}
 
-		// Summarize what we've done.
-		if flags.contains(Flags::Systemd) {
-			println!(
-				"{} unique hosts have been cast to a blackhole!",
-				NiceU64::from(len),
-			);
-		}
-		else if ! flags.contains(Flags::Quiet) {
-			Msg::success(format!(
-				"{} unique hosts have been cast to a blackhole!",
-				NiceU64::from(len),
-			)).print();
+/// # Diff.
+///
+/// Print the hosts an update would add to (+) or remove from (-) the
+/// hostfile, along with a short summary.
+///
+/// ## Errors
+///
+/// This will bubble up any errors encountered while building the list.
+fn diff_adbyss(settings: &Settings, flags: Flags) -> Result<(), AdbyssError> {
+	let old = std::fs::read_to_string(settings.hostfile()).unwrap_or_default();
+	let (new, _) = settings.build()?;
+	report_warnings(settings, flags);
+	let diff = write::diff_hosts(&old, &new);
+
+	let mut handle = BufWriter::new(std::io::stdout().lock());
+	for (add, host) in &diff {
+		let _res = writeln!(handle, "{}{host}", if *add { '+' } else { '-' });
+	}
+	let _res = handle.flush();
+	drop(handle);
+
+	if diff.is_empty() { Msg::success("The hostfile is already up to date.").eprint(); }
+	else {
+		let added = diff.iter().filter(|(add, _)| *add).count();
+		Msg::info(format!(
+			"An update would add {} and remove {} hosts.",
+			NiceU64::from(added),
+			NiceU64::from(diff.len() - added),
+		)).eprint();
+	}
+
+	Ok(())
+}
+
+/// # Edit Lists.
+///
+/// Add or remove the hosts from the main config's include/exclude lists, and
+/// let the user know how it went.
+///
+/// ## Errors
+///
+/// This will bubble up any errors encountered while editing the config.
+fn edit_lists(config: Option<&str>, edit: ListEdit, hosts: &[Domain], flags: Flags)
+-> Result<(), AdbyssError> {
+	let dst = config.unwrap_or(Settings::DEFAULT_CONFIG);
+	let list = hosts.iter().map(Domain::as_str).collect::<Vec<_>>().join(", ");
+	let changed = edit.apply_file(Path::new(dst), hosts)?;
+
+	// Entries in the drop-ins have to be dealt with by hand.
+	let held = edit.held_elsewhere(Path::new(dst), hosts)?;
+	for (host, file) in &held {
+		Msg::warning(format!(
+			"{host} is also in the {} list of {}; edit that file to remove it.",
+			edit.remove_from(),
+			file.display(),
+		)).eprint();
+	}
+
+	if changed {
+		let msg = match edit {
+			ListEdit::Allow => format!("Allowed {list}."),
+			ListEdit::Block => format!("Blocked {list}."),
+			ListEdit::Unallow => format!("Removed {list} from the exclude list."),
+			ListEdit::Unblock => format!("Removed {list} from the include list."),
+		};
+		Msg::success(format!(concat!("{} ", dim!("({})")), msg, dst)).eprint();
+		if ! flags.contains(Flags::Update) {
+			Msg::info("Run adbyss (or pass --update) to apply the changes to the hostfile.").eprint();
 		}
 	}
+	else if held.is_empty() {
+		Msg::notice(format!("{dst} already had that covered; nothing to change.")).eprint();
+	}
 
 	Ok(())
 }
@@ -235,36 +751,400 @@ fn check_internet() -> Result<(), AdbyssError> {
 	}
 }
 
-/// # Require Root.
+/// # Show.
+///
+/// Print the shitlist to STDOUT in the chosen format.
+///
+/// ## Errors
+///
+/// This will bubble up any errors encountered while building the list.
+fn show_adbyss(settings: &Settings, format: Format, flags: Flags)
+-> Result<(), AdbyssError> {
+	let mut shitlist = settings.entries()?;
+	if shitlist.is_empty() { return Err(AdbyssError::NoShitlist); }
+
+	// Block suffixes outright and drop redundant subdomains if the format
+	// can handle it.
+	let mut suffixes = filter::Suffixes::default();
+	if format.wildcard() {
+		suffixes = settings.block_suffixes();
+		let collapsed = filter::collapse(&mut shitlist, &suffixes);
+		if collapsed != 0 && ! flags.contains(Flags::Quiet) {
+			Msg::info(format!(
+				"Collapsed {} redundant subdomain(s).",
+				NiceU64::from(collapsed),
+			)).eprint();
+		}
+	}
+
+	let handle = BufWriter::new(std::io::stdout().lock());
+	let _res = format.write(&shitlist, &suffixes, settings.cnames(), handle);
+	report_run(settings, flags)
+}
+
+/// # Write.
+///
+/// Write the shitlist to the hostfile and summarize the results.
+///
+/// ## Errors
+///
+/// This will bubble up any errors encountered while building or writing the
+/// list, or running the post-write hooks.
+fn write_adbyss(settings: &Settings, flags: Flags) -> Result<(), AdbyssError> {
+	let summary = settings.write(flags.contains(Flags::Yes))?;
+	let len = NiceU64::from(summary.len());
+
+	// Summarize what we've done.
+	if summary.changed() {
+		print_summary(&format!("{len} unique hosts have been cast to a blackhole!"), flags);
+	}
+	else {
+		print_summary(&format!("Already up to date; {len} unique hosts remain blackholed."), flags);
+	}
+
+	report_run(settings, flags)?;
+
+	summarize_hooks(summary.hooks(), flags)
+}
+
+/// # Check Host.
+///
+/// Report whether or not the host would be blocked, and if so, by which
+/// sources and under which categories, and the tracker behind it, if any.
+///
+/// ## Errors
+///
+/// This will bubble up any errors encountered while building the list.
+fn check_host(settings: &Settings, dom: &Domain, flags: Flags) -> Result<(), AdbyssError> {
+	let list = settings.entries()?;
+	report_warnings(settings, flags);
+	match list.binary_search_by(|(d, _)| d.cmp(dom)) {
+		Ok(idx) => {
+			let prov = list[idx].1;
+			let categories = prov.categories().collect::<Vec<_>>();
+			println!("{dom} is blocked.");
+			println!("    Sources: {}", prov.names().collect::<Vec<_>>().join(", "));
+			if ! categories.is_empty() {
+				println!(" Categories: {}", categories.join(", "));
+			}
+			if let Some(target) = settings.cnames().get(dom.as_str()) {
+				println!("      CNAME: {target}");
+			}
+		},
+		Err(_) => { println!("{dom} is not blocked."); },
+	}
+
+	Ok(())
+}
+
+/// # Report Run.
+///
+/// Warn about any empty CNAME sources or dropped protected hosts, summarize
+/// any entries rejected during the build, saving them to disk if configured,
+/// and report on the regular expressions.
+///
+/// ## Errors
+///
+/// This will return an error if the rejects cannot be saved.
+fn report_run(settings: &Settings, flags: Flags) -> Result<(), AdbyssError> {
+	report_warnings(settings, flags);
+
+	let (rejected, dst) = settings.rejected();
+	if let Some(dst) = dst {
+		rejected.save(dst)?;
+		settings.stats().wrote("rejected", dst);
+	}
+
+	if ! flags.contains(Flags::Quiet) && ! rejected.is_empty() {
+		for line in rejected.summary() {
+			let msg = format!("Rejected {line}.");
+			if flags.contains(Flags::Systemd) { eprintln!("{msg}"); }
+			else { Msg::notice(msg).eprint(); }
+		}
+		if let Some(dst) = dst {
+			let msg = format!("Rejected entries saved to {}.", dst.display());
+			if flags.contains(Flags::Systemd) { eprintln!("{msg}"); }
+			else { Msg::notice(msg).eprint(); }
+		}
+	}
+
+	report_regexes(settings, flags);
+	Ok(())
+}
+
+/// # Report Build Warnings.
+///
+/// Warn about any CNAME sources that yielded nothing, and any protected hosts
+/// that were dropped from the blocklist, unless quiet.
+fn report_warnings(settings: &Settings, flags: Flags) {
+	if flags.contains(Flags::Quiet) { return; }
+
+	let cnames = settings.stats().empty_cnames().into_iter().map(|src| format!(
+		"CNAME source {src} contained no hosts; is it in a supported format?"
+	));
+	let protected = settings.stats().protected().into_iter().map(|d| format!(
+		"Protected host {d} turned up in the blocklist; skipping it."
+	));
+	for msg in cnames.chain(protected) {
+		if flags.contains(Flags::Systemd) { eprintln!("{msg}"); }
+		else { Msg::warning(msg).eprint(); }
+	}
+}
+
+/// # Report Regular Expressions.
+///
+/// Print the number of hosts matched by each `regexclude` and `regexinclude`
+/// pattern to STDERR, warning about any that matched nothing or contain
+/// uppercase characters that can never match.
+fn report_regexes(settings: &Settings, flags: Flags) {
+	if flags.contains(Flags::Quiet) { return; }
+
+	let (regexclude, regexinclude) = settings.regexes();
+	for (key, set) in [("regexclude", regexclude), ("regexinclude", regexinclude)] {
+		for (pat, hits) in set.stats() {
+			let (warn, msg) =
+				if filter::has_uppercase(pat) {
+					(true, format!("{key} '{pat}' contains uppercase characters, but hosts are always lowercase."))
+				}
+				else if hits == 0 { (true, format!("{key} '{pat}' matched nothing.")) }
+				else {
+					(false, format!(
+						"{key} '{pat}' matched {} host{}.",
+						NiceU64::from(hits),
+						if hits == 1 { "" } else { "s" },
+					))
+				};
+
+			if flags.contains(Flags::Systemd) { eprintln!("{msg}"); }
+			else if warn { Msg::warning(msg).eprint(); }
+			else { Msg::info(msg).eprint(); }
+		}
+	}
+}
+
+/// # Pause.
 ///
-/// This will restart the command with root privileges if necessary, or fail
-/// if that doesn't work.
-fn require_root() -> Result<(), AdbyssError> {
+/// Stash the Adbyss section for `secs` seconds, and try to schedule its
+/// return.
+fn pause_adbyss(settings: &Settings, secs: u32, args: &[String], flags: Flags)
+-> Result<(), AdbyssError> {
+	let (pause, hooks) = settings.pause(secs, flags.contains(Flags::Yes))?;
+	let scheduled = pause::schedule_resume(&pause, args);
+	print_summary(
+		&format!(
+			"Adbyss is paused until {} UTC{}",
+			FmtUtc2k::from(pause.until()),
+			if scheduled { "." } else { "; the first run after that will restore it." },
+		),
+		flags,
+	);
+	summarize_hooks(&hooks, flags)
+}
+
+/// # Restore.
+///
+/// Put the hostfile backup back.
+fn restore_adbyss(settings: &Settings, flags: Flags) -> Result<(), AdbyssError> {
+	let hooks = settings.restore(flags.contains(Flags::Yes))?;
+	print_summary(&format!("{} has been restored from backup.", settings.hostfile().display()), flags);
+	summarize_hooks(&hooks, flags)
+}
+
+/// # Resume.
+///
+/// Restore the stashed Adbyss section, if any.
+fn resume_adbyss(settings: &Settings, flags: Flags) -> Result<(), AdbyssError> {
+	let Some(hooks) = settings.resume()? else {
+		print_summary("Adbyss is not paused.", flags);
+		return Ok(());
+	};
+
+	print_summary("Adbyss has been resumed.", flags);
+	summarize_hooks(&hooks, flags)
+}
+
+/// # Still Paused?
+///
+/// Returns `true` if Adbyss is paused and the deadline hasn't passed yet. If
+/// it has, the stash is restored (and `false` returned) so the update can
+/// continue as normal.
+fn still_paused(settings: &Settings, flags: Flags) -> Result<bool, AdbyssError> {
+	let Some(pause) = settings.paused() else { return Ok(false); };
+	if pause.expired() {
+		let hooks = settings.resume()?.unwrap_or_default();
+		summarize_hooks(&hooks, flags)?;
+		Ok(false)
+	}
+	else {
+		print_summary(
+			&format!(
+				"Adbyss is paused until {} UTC; skipping update.",
+				FmtUtc2k::from(pause.until()),
+			),
+			flags,
+		);
+		Ok(true)
+	}
+}
+
+/// # Print Status.
+///
+/// Report on the current state of the hostfile's Adbyss section, if any.
+fn print_status(settings: &Settings) -> Result<(), AdbyssError> {
+	/// # Yes/No/Unknown.
+	const fn yes_no(v: Option<bool>, yes: &'static str, no: &'static str) -> &'static str {
+		match v {
+			Some(true) => yes,
+			Some(false) => no,
+			None => "unknown",
+		}
+	}
+
+	let meta = settings.status()?;
+	println!("Hostfile: {}", settings.hostfile().display());
+	if let Some(pause) = settings.paused() {
+		println!("  Status: paused until {} UTC", FmtUtc2k::from(pause.until()));
+		return Ok(());
+	}
+	let Some(meta) = meta else {
+		println!("  Status: inactive");
+		return Ok(());
+	};
+
+	println!("  Status: active");
+	if let Some(v) = meta.updated() { println!(" Updated: {} UTC", FmtUtc2k::from(v)); }
+	if let Some(v) = meta.blocked() { println!(" Blocked: {} hosts", NiceU64::from(v)); }
+	if let Some(v) = meta.version() { println!(" Version: {v}"); }
+	if let Some(v) = meta.sources() { println!(" Sources: {v}"); }
+	println!(
+		"  Config: {}",
+		yes_no(
+			meta.config().map(|v| v != settings.config_hash()),
+			"changed since last run",
+			"unchanged",
+		),
+	);
+	println!("  Edited: {}", yes_no(meta.edited(), "yes", "no"));
+
+	Ok(())
+}
+
+/// # Print Summary.
+///
+/// Print a plain success message for systemd, or a pretty one for humans,
+/// unless quiet. (JSON reporting implies quiet, so this never pollutes the
+/// report.)
+fn print_summary(msg: &str, flags: Flags) {
+	if flags.contains(Flags::Quiet) { return; }
+
+	if flags.contains(Flags::Systemd) { println!("{msg}"); }
+	else { Msg::success(msg).print(); }
+}
+
+/// # Summarize Hooks.
+///
+/// Print the status of each post-write hook that was run (unless quiet), and
+/// return an error if any of them failed.
+fn summarize_hooks(hooks: &[HookStatus<'_>], flags: Flags) -> Result<(), AdbyssError> {
+	let mut failed = 0_usize;
+	for h in hooks {
+		if h.failed() { failed += 1; }
+
+		if flags.contains(Flags::Quiet) { continue; }
+
+		if flags.contains(Flags::Systemd) {
+			println!("Hook {}: {h}", if h.failed() { "failed" } else { "succeeded" });
+		}
+		else if h.failed() { Msg::warning(format!("Hook failed: {h}")).eprint(); }
+		else { Msg::info(format!("Hook succeeded: {h}")).print(); }
+	}
+
+	if failed == 0 { Ok(()) }
+	else { Err(AdbyssError::PostWrite(format!("{failed}/{}", hooks.len()))) }
+}
+
+/// # Require Root (Maybe).
+///
+/// If the current user can't write to all of the paths, restart the command
+/// with root privileges — via `sudo`, `doas`, `run0`, or `pkexec`, whichever
+/// turns up first — or fail if that doesn't work (or `--no-escalate` was
+/// set).
+fn require_root<P, I>(paths: I, flags: Flags) -> Result<(), AdbyssError>
+where P: AsRef<Path>, I: IntoIterator<Item=P> {
 	use nix::unistd::Uid;
 
 	// We're already root.
 	if Uid::effective().is_root() {
-		if Uid::current().is_root() { Ok(()) }
+		if Uid::current().is_root() { return Ok(()); }
 		// Almost… we just need to SETUID.
-		else {
-			nix::unistd::setuid(Uid::from_raw(0)).map_err(|_| AdbyssError::Root)
-		}
+		return nix::unistd::setuid(Uid::from_raw(0))
+			.map_err(|_| AdbyssError::Root("setuid".to_owned()));
 	}
-	// We need to escalate!
+
+	// Do we need to escalate?
+	let Some(path) = paths.into_iter().find(|p| ! writable(p.as_ref())) else {
+		return Ok(());
+	};
+	let path = path.as_ref().to_string_lossy().into_owned();
+	if flags.contains(Flags::NoEscalate) { return Err(AdbyssError::Root(path)); }
+	let (Some(bin), Ok(exe)) = (escalator(), std::env::current_exe()) else {
+		return Err(AdbyssError::Root(path));
+	};
+
+	// Relaunch the command with escalation. The profile is passed explicitly
+	// since the environment generally doesn't survive the trip.
+	let mut args: Vec<OsString> = std::env::args_os().skip(1).collect();
+	if
+		! args.iter().any(|a| a.to_str().is_some_and(|a| a.starts_with("--profile"))) &&
+		let Some(profile) = std::env::var_os(config::PROFILE_ENV).filter(|p| ! p.is_empty())
+	{
+		args.push(OsString::from("--profile"));
+		args.push(profile);
+	}
+	let mut child = std::process::Command::new(bin)
+		.arg(exe)
+		.args(args)
+		.spawn()
+		.map_err(|_| AdbyssError::Root(path.clone()))?;
+
+	// Wait to see what happens.
+	let exit = child.wait().map_err(|_| AdbyssError::Root(path))?;
+
+	// Exit this (the old) instance with the same code, unless it was killed
+	// by a signal, which deserves its own error.
+	if let Some(code) = exit.code() { std::process::exit(code); }
+	Err(AdbyssError::Killed(exit.signal().map_or_else(
+		|| "unknown signal".to_owned(),
+		|s| format!("signal {s}"),
+	)))
+}
+
+/// # Escalator.
+///
+/// Find the first available privilege escalation program. Only the standard
+/// system directories are searched; `$PATH` is user-controlled, and thus not
+/// to be trusted with root.
+fn escalator() -> Option<PathBuf> {
+	["sudo", "doas", "run0", "pkexec"].into_iter().find_map(|bin|
+		["/usr/bin", "/bin", "/usr/local/bin"].into_iter()
+			.map(|dir| Path::new(dir).join(bin))
+			.find(|p| p.is_file())
+	)
+}
+
+/// # Writable?
+///
+/// Returns `true` if the current user can write to the path, or create it if
+/// it doesn't exist yet.
+fn writable(path: &Path) -> bool {
+	use nix::unistd::{
+		access,
+		AccessFlags,
+	};
+
+	if path.exists() { access(path, AccessFlags::W_OK).is_ok() }
 	else {
-		// Relaunch the command with sudo escalation.
-		let mut child = Command::new("/usr/bin/sudo")
-			.args(std::env::args())
-			.spawn()
-			.map_err(|_| AdbyssError::Root)?;
-
-		// Wait to see what happens.
-		let exit = child.wait().map_err(|_| AdbyssError::Root)?;
-
-		// Exit this (the old) instance with an appropriate code.
-		std::process::exit(
-			if exit.success() { 0 }
-			else { exit.code().unwrap_or(1) }
-		);
+		let dir = path.parent().filter(|p| ! p.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+		access(dir, AccessFlags::W_OK).is_ok()
 	}
 }
@@ -0,0 +1,176 @@
+/*!
+# Adbyss: Pause
+*/
+
+use crate::AdbyssError;
+use std::{
+	path::{
+		Path,
+		PathBuf,
+	},
+	process::{
+		Command,
+		Stdio,
+	},
+};
+
+
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+/// # Pause.
+///
+/// When Adbyss is paused, its section is removed from the hostfile and
+/// stashed alongside it — `/etc/hosts.adbyss.paused` — together with the
+/// deadline (unixtime) at which it should be restored.
+pub(super) struct Pause {
+	/// # Restore At.
+	until: u32,
+
+	/// # The Stashed Adbyss Section.
+	block: String,
+}
+
+impl Pause {
+	/// # New.
+	pub(super) fn new(secs: u32, block: String) -> Self {
+		Self {
+			until: utc2k::unixtime().saturating_add(secs),
+			block,
+		}
+	}
+
+	/// # Load.
+	///
+	/// Return the stashed pause for the given hostfile, if any.
+	pub(super) fn load(hostfile: &Path) -> Option<Self> {
+		let raw = std::fs::read_to_string(stash_path(hostfile)).ok()?;
+		let (until, block) = raw.split_once('\n')?;
+		let until = until.trim().parse::<u32>().ok()?;
+		Some(Self { until, block: block.to_owned() })
+	}
+
+	/// # Save.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the stash cannot be written.
+	pub(super) fn save(&self, hostfile: &Path) -> Result<(), AdbyssError> {
+		let mut out = self.until.to_string();
+		out.push('\n');
+		out.push_str(&self.block);
+		crate::write::write_to_file(&stash_path(hostfile), out.as_bytes())
+	}
+
+	/// # Remove.
+	///
+	/// Delete the stash for the given hostfile, if any.
+	pub(super) fn remove(hostfile: &Path) {
+		let _res = std::fs::remove_file(stash_path(hostfile));
+	}
+}
+
+impl Pause {
+	/// # The Stashed Section.
+	pub(super) fn block(&self) -> &str { &self.block }
+
+	/// # Expired?
+	pub(super) fn expired(&self) -> bool { self.until <= utc2k::unixtime() }
+
+	/// # Restore At.
+	pub(super) const fn until(&self) -> u32 { self.until }
+}
+
+
+
+/// # Parse Duration.
+///
+/// Parse a duration like `90s`, `15m`, `2h`, or `1d` into seconds. Bare
+/// numbers are treated as seconds.
+pub(super) fn parse_duration(src: &str) -> Option<u32> {
+	let src = src.trim();
+	let mul = match src.as_bytes().last() {
+		Some(b's') => 1,
+		Some(b'm') => 60,
+		Some(b'h') => 3600,
+		Some(b'd') => 86_400,
+		_ => 0,
+	};
+	let num = if mul == 0 { src } else { &src[..src.len() - 1] };
+
+	num.parse::<u32>().ok()
+		.and_then(|n| n.checked_mul(mul.max(1)))
+		.filter(|&n| n != 0)
+}
+
+/// # Schedule Resume.
+///
+/// Try to schedule a regular Adbyss run for the moment the pause expires using
+/// a transient systemd timer. (That run will restore the stashed section and
+/// bring it up-to-date.)
+///
+/// The arguments — config path, profile, overrides, etc. — are passed along
+/// to that run so it uses the same settings.
+///
+/// Returns `true` if successful. If not, the pause will still be honored —
+/// and cleared — by the first invocation after the deadline.
+pub(super) fn schedule_resume(pause: &Pause, args: &[String]) -> bool {
+	let Ok(exe) = std::env::current_exe() else { return false; };
+	let secs = pause.until.saturating_sub(utc2k::unixtime()).max(1);
+
+	let mut cmd = Command::new("systemd-run");
+	cmd.arg(format!("--unit=adbyss-resume-{}", pause.until))
+		.arg(format!("--on-active={secs}s"))
+		.args(["--timer-property=AccuracySec=1s", "--collect", "--quiet"])
+		.arg(exe)
+		.arg("--systemd")
+		.args(args);
+
+	cmd.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.status()
+		.is_ok_and(|s| s.success())
+}
+
+/// # Stash Path.
+pub(super) fn stash_path(hostfile: &Path) -> PathBuf {
+	let mut out = hostfile.to_path_buf();
+	out.as_mut_os_string().push(".adbyss.paused");
+	out
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_parse_duration() {
+		assert_eq!(parse_duration("90"), Some(90));
+		assert_eq!(parse_duration("90s"), Some(90));
+		assert_eq!(parse_duration("15m"), Some(900));
+		assert_eq!(parse_duration(" 2h "), Some(7200));
+		assert_eq!(parse_duration("1d"), Some(86_400));
+		assert_eq!(parse_duration("0m"), None);
+		assert_eq!(parse_duration("m"), None);
+		assert_eq!(parse_duration("15 minutes"), None);
+		assert_eq!(parse_duration("9999999999"), None);
+	}
+
+	#[test]
+	fn t_stash() {
+		let hostfile = std::env::temp_dir().join(format!("adbyss-t-stash-{}.hosts", std::process::id()));
+		let pause = Pause::new(900, "##########\n# ADBYSS #\n".to_owned());
+		assert!(! pause.expired());
+
+		let saved = pause.save(&hostfile);
+		let loaded = Pause::load(&hostfile);
+		Pause::remove(&hostfile);
+		let removed = Pause::load(&hostfile);
+
+		saved.expect("Unable to save pause.");
+		assert_eq!(loaded, Some(pause));
+		assert!(removed.is_none());
+	}
+}
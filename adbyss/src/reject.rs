@@ -0,0 +1,280 @@
+/*!
+# Adbyss: Rejected Entries
+*/
+
+use adbyss_psl::Domain;
+use crate::{
+	AdbyssError,
+	MAX_LINE,
+	Source,
+	source::Provenance,
+};
+use std::{
+	cell::RefCell,
+	fmt,
+	path::Path,
+};
+
+
+
+/// # Expected Non-Hosts.
+///
+/// Hosts-formatted lists typically start with a few lines like these for the
+/// benefit of anybody using them directly; they aren't worth reporting.
+const EXPECTED: [&str; 5] = [
+	"0.0.0.0",
+	"broadcasthost",
+	"local",
+	"localhost",
+	"localhost.localdomain",
+];
+
+
+
+#[derive(Debug, Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
+/// # Rejection Reason.
+pub(super) enum Reason {
+	/// # Invalid Label(s).
+	InvalidLabel,
+
+	/// # Unknown Suffix.
+	UnknownSuffix,
+
+	/// # Bare Public Suffix.
+	BareSuffix,
+
+	/// # Too Long.
+	TooLong,
+}
+
+impl fmt::Display for Reason {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl Reason {
+	/// # All Reasons.
+	const ALL: [Self; 4] = [
+		Self::InvalidLabel,
+		Self::UnknownSuffix,
+		Self::BareSuffix,
+		Self::TooLong,
+	];
+
+	/// # As Str.
+	pub(super) const fn as_str(self) -> &'static str {
+		match self {
+			Self::InvalidLabel => "invalid label",
+			Self::UnknownSuffix => "unknown suffix",
+			Self::BareSuffix => "bare suffix",
+			Self::TooLong => "too long",
+		}
+	}
+
+	/// # Classify.
+	///
+	/// Figure out why an entry was — or would be — rejected, returning `None`
+	/// if it is perfectly fine.
+	pub(super) fn classify(src: &str) -> Option<Self> {
+		if let Some(dom) = Domain::new(src) {
+			return if MAX_LINE < dom.len() { Some(Self::TooLong) } else { None };
+		}
+
+		let src = src.trim().trim_end_matches('.').to_ascii_lowercase();
+		if 253 < src.len() { Some(Self::TooLong) }
+		else if Domain::new(format!("x.{src}")).is_some_and(|d| d.suffix() == src) {
+			Some(Self::BareSuffix)
+		}
+		else if src.split('.').all(valid_label) { Some(Self::UnknownSuffix) }
+		else { Some(Self::InvalidLabel) }
+	}
+}
+
+
+
+#[derive(Clone, Default)]
+/// # Rejected Entries.
+///
+/// This holds the entries rejected during the last shitlist build, along
+/// with where they came from and why.
+pub(super) struct Rejects(RefCell<Vec<(String, Provenance, Reason)>>);
+
+impl fmt::Debug for Rejects {
+	/// # Debug.
+	///
+	/// The contents are run-specific, so are omitted.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("Rejects")
+	}
+}
+
+impl Rejects {
+	/// # Clear.
+	pub(super) fn clear(&self) { self.0.borrow_mut().truncate(0); }
+
+	/// # Is Empty?
+	pub(super) fn is_empty(&self) -> bool { self.0.borrow().is_empty() }
+
+	/// # Length.
+	pub(super) fn len(&self) -> usize { self.0.borrow().len() }
+
+	/// # Push.
+	///
+	/// Classify and record the entry, unless it is one of the usual
+	/// non-hosts.
+	pub(super) fn push(&self, src: &str, prov: Provenance) {
+		if ! EXPECTED.contains(&src) && let Some(reason) = Reason::classify(src) {
+			self.0.borrow_mut().push((src.to_owned(), prov, reason));
+		}
+	}
+
+	/// # Summary.
+	///
+	/// Return a line for each source with rejections, like `Yoyo: 3 (2
+	/// invalid label, 1 unknown suffix)`.
+	pub(super) fn summary(&self) -> Vec<String> {
+		let list = self.0.borrow();
+		source_names().filter_map(|(name, prov)| {
+			let mut counts = [0_usize; 4];
+			for (_, p, r) in list.iter() {
+				if p.contains(prov) { counts[*r as usize] += 1; }
+			}
+
+			let total: usize = counts.iter().sum();
+			if total == 0 { return None; }
+
+			let detail = Reason::ALL.into_iter()
+				.zip(counts)
+				.filter(|(_, n)| *n != 0)
+				.map(|(r, n)| format!("{n} {r}"))
+				.collect::<Vec<_>>()
+				.join(", ");
+			Some(format!("{name}: {total} ({detail})"))
+		})
+			.collect()
+	}
+
+	/// # Save.
+	///
+	/// Write the rejected entries to a tab-separated file — source, reason,
+	/// entry — suitable for attaching to upstream bug reports.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the file cannot be written.
+	pub(super) fn save(&self, dst: &Path) -> Result<(), AdbyssError> {
+		let mut list = self.0.borrow().clone();
+		list.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+		let mut out = String::from("source\treason\tentry\n");
+		for (name, prov) in source_names() {
+			for (entry, _, reason) in list.iter().filter(|(_, p, _)| p.contains(prov)) {
+				out.push_str(name);
+				out.push('\t');
+				out.push_str(reason.as_str());
+				out.push('\t');
+				out.push_str(entry);
+				out.push('\n');
+			}
+		}
+
+		crate::write::write_to_file(dst, out.as_bytes())
+	}
+}
+
+
+
+/// # Source Names.
+///
+/// Return an iterator over each source (and the user CNAME lists and
+/// includes), along with their provenance.
+fn source_names() -> impl Iterator<Item=(&'static str, Provenance)> {
+	Source::ALL.into_iter()
+		.map(|s| (s.as_str(), Provenance::from(s)))
+		.chain([("CNAME", Provenance::CNAME), ("Include", Provenance::INCLUDE)])
+}
+
+/// # Valid Label?
+///
+/// Returns `true` if the label is plain ASCII and otherwise looks okay.
+fn valid_label(label: &str) -> bool {
+	let bytes = label.as_bytes();
+	! bytes.is_empty() &&
+	bytes.len() <= 63 &&
+	bytes[0].is_ascii_alphanumeric() &&
+	bytes[bytes.len() - 1].is_ascii_alphanumeric() &&
+	bytes.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'-')
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_classify() {
+		for (raw, expected) in [
+			("blobfolio.com", None),
+			("www.blobfolio.co.uk", None),
+			("com", Some(Reason::BareSuffix)),
+			("co.uk", Some(Reason::BareSuffix)),
+			("blobfolio.notarealsuffix", Some(Reason::UnknownSuffix)),
+			("intranet", Some(Reason::UnknownSuffix)),
+			("-blobfolio.com", Some(Reason::InvalidLabel)),
+			("blob folio.com", Some(Reason::InvalidLabel)),
+			("blobfolio..com", Some(Reason::InvalidLabel)),
+		] {
+			assert_eq!(Reason::classify(raw), expected, "{raw}");
+		}
+
+		// Too long, for us or anybody.
+		let long = format!("{}.blobfolio.com", ["abcdefghi"; 24].join("."));
+		assert!(MAX_LINE < long.len() && long.len() <= 253);
+		assert_eq!(Reason::classify(&long), Some(Reason::TooLong));
+		let long = format!("{}.blobfolio.com", ["abcdefghi"; 25].join("."));
+		assert_eq!(Reason::classify(&long), Some(Reason::TooLong));
+	}
+
+	#[test]
+	fn t_rejects() {
+		let rejects = Rejects::default();
+		let mut both = Provenance::from(Source::AdAway);
+		both |= Provenance::from(Source::Yoyo);
+
+		rejects.push("localhost", Provenance::from(Source::AdAway));
+		rejects.push("blobfolio.com", Provenance::from(Source::AdAway));
+		rejects.push("com", both);
+		rejects.push("-ads.com", Provenance::from(Source::Yoyo));
+		rejects.push("batman", Provenance::INCLUDE);
+
+		assert_eq!(
+			rejects.summary(),
+			[
+				"AdAway: 1 (1 bare suffix)",
+				"Yoyo: 2 (1 invalid label, 1 bare suffix)",
+				"Include: 1 (1 unknown suffix)",
+			],
+		);
+
+		let dst = std::env::temp_dir().join(format!("adbyss-t-rejects-{}.tsv", std::process::id()));
+		let saved = rejects.save(&dst);
+		let raw = std::fs::read_to_string(&dst);
+		let _res = std::fs::remove_file(&dst);
+
+		saved.expect("Unable to save rejects.");
+		assert_eq!(
+			raw.expect("Unable to read rejects."),
+			"source\treason\tentry
+AdAway\tbare suffix\tcom
+Yoyo\tinvalid label\t-ads.com
+Yoyo\tbare suffix\tcom
+Include\tunknown suffix\tbatman
+",
+		);
+
+		rejects.clear();
+		assert!(rejects.is_empty());
+	}
+}
@@ -0,0 +1,419 @@
+/*!
+# Adbyss: Run Report
+*/
+
+use crate::{
+	AdbyssError,
+	source::{
+		Origin,
+		Provenance,
+	},
+};
+use std::{
+	cell::RefCell,
+	fmt::{
+		self,
+		Write,
+	},
+	path::{
+		Path,
+		PathBuf,
+	},
+	time::Duration,
+};
+
+
+
+#[derive(Clone, Default)]
+/// # Run Stats.
+///
+/// This holds the particulars of the last shitlist build — what was fetched,
+/// how it parsed, what was written — for the JSON run report.
+pub(super) struct RunStats(RefCell<Stats>);
+
+impl fmt::Debug for RunStats {
+	/// # Debug.
+	///
+	/// The contents are run-specific, so are omitted.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("RunStats")
+	}
+}
+
+impl RunStats {
+	/// # Clear.
+	pub(super) fn clear(&self) { *self.0.borrow_mut() = Stats::default(); }
+
+	/// # Empty CNAME Sources.
+	///
+	/// Return the CNAME sources that were fetched just fine, but yielded no
+	/// hosts, likely because they're in some unsupported format.
+	pub(super) fn empty_cnames(&self) -> Vec<String> {
+		self.0.borrow().fetches.iter()
+			.filter(|f| f.source == "cname" && f.origin.is_ok() && f.parsed == 0)
+			.map(|f| f.url.clone())
+			.collect()
+	}
+
+	/// # Push Fetch.
+	///
+	/// Record a list fetch, returning its index.
+	pub(super) fn push_fetch(&self, fetch: Fetch) -> usize {
+		let mut stats = self.0.borrow_mut();
+		stats.fetches.push(fetch);
+		stats.fetches.len() - 1
+	}
+
+	/// # Set Parsed (Per Fetch).
+	pub(super) fn set_parsed(&self, idx: usize, parsed: usize) {
+		if let Some(fetch) = self.0.borrow_mut().fetches.get_mut(idx) {
+			fetch.parsed = parsed;
+		}
+	}
+
+	/// # Protected Hosts.
+	///
+	/// Return the protected hosts dropped from the last build, if any.
+	pub(super) fn protected(&self) -> Vec<String> {
+		self.0.borrow().protected.clone()
+	}
+
+	/// # Update.
+	///
+	/// Update the totals.
+	pub(super) fn update<F: FnOnce(&mut Stats)>(&self, cb: F) {
+		cb(&mut self.0.borrow_mut());
+	}
+
+	/// # Wrote.
+	///
+	/// Record an output target, like `hostfile` or `backup`.
+	pub(super) fn wrote(&self, target: &'static str, path: &Path) {
+		self.0.borrow_mut().written.push((target, path.to_path_buf()));
+	}
+
+	/// # Render (JSON).
+	///
+	/// Render the report as JSON, noting the command, the start time (unix
+	/// seconds), the total run time, and the result.
+	pub(super) fn render(
+		&self,
+		command: &str,
+		started: u32,
+		elapsed: Duration,
+		res: &Result<(), AdbyssError>,
+	) -> String {
+		let stats = self.0.borrow();
+		let mut out = String::with_capacity(1024);
+
+		let _res = write!(
+			out,
+			concat!(
+				"{{\n",
+				"  \"version\": \"", env!("CARGO_PKG_VERSION"), "\",\n",
+				"  \"command\": {},\n",
+				"  \"started\": {},\n",
+				"  \"success\": {},\n",
+				"  \"error\": ",
+			),
+			JsonStr(command),
+			started,
+			res.is_ok(),
+		);
+		match res {
+			Ok(()) => { out.push_str("null"); },
+			Err(e) => {
+				let _res = write!(
+					out,
+					r#"{{"code": {}, "message": {}, "detail": "#,
+					e.exit_code(),
+					JsonStr(e.as_str()),
+				);
+				push_opt(&mut out, e.detail().as_deref());
+				out.push('}');
+			},
+		}
+
+		// Sources.
+		out.push_str(",\n  \"sources\": [");
+		for (k, fetch) in stats.fetches.iter().enumerate() {
+			out.push_str(if k == 0 { "\n    " } else { ",\n    " });
+			fetch.render(&mut out);
+		}
+		out.push_str(if stats.fetches.is_empty() { "]" } else { "\n  ]" });
+
+		// Counts.
+		let _res = write!(
+			out,
+			concat!(
+				",\n  \"counts\": {{",
+				"\"parsed\": {}, \"rejected\": {}, \"expanded\": {}, ",
+				"\"excluded\": {}, \"protected\": {}, \"unique\": {}}}",
+			),
+			stats.parsed,
+			stats.rejected,
+			stats.expanded,
+			stats.excluded,
+			stats.protected.len(),
+			stats.unique,
+		);
+
+		// Outputs.
+		out.push_str(",\n  \"written\": [");
+		for (k, (target, path)) in stats.written.iter().enumerate() {
+			let _res = write!(
+				out,
+				r#"{}{{"target": {}, "path": {}}}"#,
+				if k == 0 { "\n    " } else { ",\n    " },
+				JsonStr(target),
+				JsonStr(&path.to_string_lossy()),
+			);
+		}
+		out.push_str(if stats.written.is_empty() { "]" } else { "\n  ]" });
+
+		// Timings.
+		let _res = write!(
+			out,
+			",\n  \"timings\": {{\"build_ms\": {}, \"total_ms\": {}}}\n}}\n",
+			stats.build.as_millis(),
+			elapsed.as_millis(),
+		);
+
+		out
+	}
+}
+
+
+
+#[derive(Debug, Clone, Default)]
+/// # Stats.
+///
+/// The totals for a run, along with the individual fetches and writes.
+pub(super) struct Stats {
+	/// # Fetches.
+	fetches: Vec<Fetch>,
+
+	/// # Unique Entries Parsed.
+	pub(super) parsed: usize,
+
+	/// # Unique Entries Rejected.
+	///
+	/// This is a subset of `parsed`, though the usual non-hosts like
+	/// `localhost` are ignored.
+	pub(super) rejected: usize,
+
+	/// # Hosts Added by WWW Expansion.
+	pub(super) expanded: usize,
+
+	/// # Hosts Removed by Excludes.
+	pub(super) excluded: usize,
+
+	/// # Hosts Removed for Protection.
+	pub(super) protected: Vec<String>,
+
+	/// # Unique Hosts (Final).
+	pub(super) unique: usize,
+
+	/// # Build Time.
+	pub(super) build: Duration,
+
+	/// # Written Targets.
+	written: Vec<(&'static str, PathBuf)>,
+}
+
+
+
+#[derive(Debug, Clone)]
+/// # Fetch.
+///
+/// The outcome of fetching a single source list.
+pub(super) struct Fetch {
+	/// # Source Name.
+	source: &'static str,
+
+	/// # Provenance (For the Categories).
+	prov: Provenance,
+
+	/// # URL or Path.
+	///
+	/// This is empty for the built-in list.
+	url: String,
+
+	/// # Origin or Error.
+	origin: Result<Origin, String>,
+
+	/// # Size (Bytes).
+	bytes: usize,
+
+	/// # Entries Parsed.
+	parsed: usize,
+
+	/// # Fetch Time.
+	elapsed: Duration,
+}
+
+impl Fetch {
+	/// # New.
+	pub(super) fn new(
+		source: &'static str,
+		prov: Provenance,
+		url: String,
+		origin: Result<(Origin, usize), &AdbyssError>,
+		elapsed: Duration,
+	) -> Self {
+		let (origin, bytes) = match origin {
+			Ok((origin, bytes)) => (Ok(origin), bytes),
+			Err(e) => (Err(e.detail().unwrap_or_else(|| e.as_str().to_owned())), 0),
+		};
+		Self { source, prov, url, origin, bytes, parsed: 0, elapsed }
+	}
+
+	/// # Render (JSON).
+	fn render(&self, out: &mut String) {
+		let _res = write!(
+			out,
+			r#"{{"source": {}, "categories": ["#,
+			JsonStr(self.source),
+		);
+		for (k, cat) in self.prov.categories().enumerate() {
+			if k != 0 { out.push_str(", "); }
+			let _res = write!(out, "{}", JsonStr(cat));
+		}
+		out.push_str(r#"], "url": "#);
+		push_opt(out, Some(self.url.as_str()).filter(|u| ! u.is_empty()));
+		let _res = write!(
+			out,
+			r#", "status": {}, "error": "#,
+			JsonStr(if self.origin.is_ok() { "ok" } else { "error" }),
+		);
+		push_opt(out, self.origin.as_ref().err().map(String::as_str));
+		out.push_str(r#", "origin": "#);
+		push_opt(out, self.origin.as_ref().ok().copied().map(Origin::as_str));
+		out.push_str(r#", "cache": "#);
+		push_opt(out, self.origin.as_ref().ok().copied().and_then(Origin::cache));
+		let _res = write!(
+			out,
+			r#", "bytes": {}, "parsed": {}, "ms": {}}}"#,
+			self.bytes,
+			self.parsed,
+			self.elapsed.as_millis(),
+		);
+	}
+}
+
+
+
+/// # JSON String.
+///
+/// This wrapper prints its contents as a quoted and escaped JSON string.
+struct JsonStr<'a>(&'a str);
+
+impl fmt::Display for JsonStr<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_char('"')?;
+		for c in self.0.chars() {
+			match c {
+				'"' => f.write_str("\\\"")?,
+				'\\' => f.write_str("\\\\")?,
+				'\n' => f.write_str("\\n")?,
+				'\r' => f.write_str("\\r")?,
+				'\t' => f.write_str("\\t")?,
+				c if c.is_control() => write!(f, "\\u{:04x}", u32::from(c))?,
+				c => f.write_char(c)?,
+			}
+		}
+		f.write_char('"')
+	}
+}
+
+/// # Push Optional String.
+///
+/// Write the string as JSON, or `null` if there isn't one.
+fn push_opt(out: &mut String, src: Option<&str>) {
+	if let Some(src) = src { let _res = write!(out, "{}", JsonStr(src)); }
+	else { out.push_str("null"); }
+}
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::Source;
+
+	#[test]
+	fn t_json_str() {
+		assert_eq!(JsonStr("plain").to_string(), r#""plain""#);
+		assert_eq!(
+			JsonStr("a \"b\"\\c\nd\u{1}").to_string(),
+			r#""a \"b\"\\c\nd\u0001""#,
+		);
+	}
+
+	#[test]
+	fn t_render() {
+		let stats = RunStats::default();
+		let idx = stats.push_fetch(Fetch::new(
+			"yoyo",
+			Provenance::from(Source::Yoyo),
+			"https://example.com/yoyo.txt".to_owned(),
+			Ok((Origin::Cache, 1234)),
+			Duration::from_millis(5),
+		));
+		stats.set_parsed(idx, 100);
+		let err = AdbyssError::SourceFetch("adaway".to_owned(), "HTTP 404 Not Found".to_owned());
+		stats.push_fetch(Fetch::new(
+			"adaway",
+			Provenance::from(Source::AdAway),
+			"https://example.com/adaway.txt".to_owned(),
+			Err(&err),
+			Duration::ZERO,
+		));
+		stats.update(|s| {
+			s.parsed = 100;
+			s.unique = 90;
+		});
+		stats.wrote("hostfile", Path::new("/etc/hosts"));
+
+		// A CNAME list that parsed to nothing should stand out.
+		let idx = stats.push_fetch(Fetch::new(
+			"cname",
+			Provenance::CNAME,
+			"skel/cname.txt".to_owned(),
+			Ok((Origin::File, 50)),
+			Duration::ZERO,
+		));
+		assert_eq!(stats.empty_cnames(), ["skel/cname.txt"]);
+		stats.set_parsed(idx, 3);
+		assert!(stats.empty_cnames().is_empty());
+
+		let out = stats.render("update", 0, Duration::ZERO, &Err(err));
+		assert!(
+			serde_json::from_str::<serde_json::Value>(&out).is_ok(),
+			"Invalid JSON:\n{out}",
+		);
+		assert!(out.starts_with("{\n"));
+		assert!(out.ends_with("}\n"));
+		assert!(out.contains(r#""success": false"#));
+		assert!(out.contains(r#""error": {"code": 31, "message": "Unable to fetch source.", "detail": "adaway: HTTP 404 Not Found"}"#));
+		assert!(out.contains(r#""status": "ok", "error": null, "origin": "cache", "cache": "hit", "bytes": 1234, "parsed": 100"#));
+		assert!(out.contains(r#""status": "error", "error": "adaway: HTTP 404 Not Found", "origin": null"#));
+		assert!(out.contains(r#""unique": 90"#));
+		assert!(out.contains(r#"{"target": "hostfile", "path": "/etc/hosts"}"#));
+
+		// Clearing should clear.
+		stats.clear();
+		let out = stats.render("update", 0, Duration::ZERO, &Ok(()));
+		assert!(out.contains(r#""error": null"#));
+		assert!(out.contains(r#""sources": []"#));
+		assert!(out.contains(r#""written": []"#));
+
+		// Other errors should have details too.
+		let err = AdbyssError::Write(
+			"/etc/hosts".to_owned(),
+			std::io::Error::from_raw_os_error(13).to_string(),
+		);
+		let out = stats.render("update", 0, Duration::ZERO, &Err(err));
+		assert!(out.contains(r#""detail": "/etc/hosts: Permission denied (os error 13)""#));
+	}
+}
@@ -5,31 +5,64 @@
 use adbyss_psl::Domain;
 use crate::{
 	AdbyssError,
+	cname::{
+		CnamePairs,
+		CnameSource,
+		CnameTargets,
+	},
+	config::Config,
+	filter::{
+		Include,
+		Patterns,
+		Regexes,
+		Suffixes,
+		WwwExpansion,
+	},
+	hook::{
+		Hook,
+		HookStatus,
+	},
 	MAX_LINE,
+	pause::Pause,
+	reject::Rejects,
+	report::{
+		Fetch,
+		RunStats,
+	},
 	Shitlist,
 	Source,
+	source::{
+		Categories,
+		Category,
+		List,
+		Origin,
+		Provenance,
+	},
+	write::{
+		Checksum,
+		ShitlistMeta,
+	},
 };
 use dactyl::NiceU64;
-use regex::RegexSet;
-use serde::{
-	de,
-	Deserialize,
-};
+use serde::Deserialize;
 use std::{
 	borrow::Cow,
-	collections::BTreeSet,
 	path::{
 		Path,
 		PathBuf,
 	},
 	str::Lines,
+	time::{
+		Duration,
+		Instant,
+	},
 };
 
 
 
 #[expect(clippy::struct_excessive_bools, reason = "The fields mirror our TOML config.")]
 #[derive(Debug, Clone, Deserialize)]
-#[serde(default)]
+#[serde(default, deny_unknown_fields)]
 /// # Settings.
 ///
 /// This struct holds the user's runtime preferences.
@@ -55,16 +88,62 @@ pub(super) struct Settings {
 	/// # Use Yoyo Sources?
 	source_yoyo: bool,
 
+	/// # Enabled Categories.
+	categories: Categories,
+
+	/// # CNAME-Cloaking Lists.
+	cname_sources: Vec<CnameSource>,
+
+	/// # WWW Expansion.
+	www_expansion: WwwExpansion,
+
 	/// # Domains to Exclude.
-	exclude: BTreeSet<Domain>,
+	exclude: Patterns,
 
-	#[serde(deserialize_with = "deserialize_regexclude")]
 	/// # Patterns to Exclude.
-	regexclude: Option<RegexSet>,
+	regexclude: Regexes,
+
+	/// # Patterns to Include (Despite Exclusion).
+	regexinclude: Regexes,
 
-	#[serde(deserialize_with = "deserialize_include")]
 	/// # Domains to Include.
-	include: Vec<String>,
+	include: Include,
+
+	/// # Use the Built-in Protected Hosts?
+	protect_defaults: bool,
+
+	/// # Additional Protected Hosts.
+	protect: Patterns,
+
+	/// # Abort if Protected Hosts Turn Up?
+	protect_strict: bool,
+
+	/// # Public Suffixes to Block Outright.
+	block_suffixes: Suffixes,
+
+	/// # Public Suffixes to Never Block.
+	allow_suffixes: Suffixes,
+
+	/// # Post-Write Hooks.
+	post_write: Vec<Hook>,
+
+	/// # Save Rejected Entries Here.
+	rejected_file: Option<PathBuf>,
+
+	/// # Save a JSON Run Report Here.
+	report_path: Option<PathBuf>,
+
+	#[serde(skip)]
+	/// # CNAME Targets (From the Last Build).
+	cnames: CnameTargets,
+
+	#[serde(skip)]
+	/// # Rejected Entries (From the Last Build).
+	rejected: Rejects,
+
+	#[serde(skip)]
+	/// # Run Stats (From the Last Build).
+	stats: RunStats,
 }
 
 impl Default for Settings {
@@ -77,9 +156,24 @@ impl Default for Settings {
 			source_adbyss: true,
 			source_stevenblack: true,
 			source_yoyo: true,
-			exclude: BTreeSet::new(),
-			regexclude: None,
-			include: Vec::new(),
+			categories: Categories::default(),
+			cname_sources: Vec::new(),
+			www_expansion: WwwExpansion::Strip,
+			exclude: Patterns::default(),
+			regexclude: Regexes::default(),
+			regexinclude: Regexes::default(),
+			include: Include::default(),
+			protect_defaults: true,
+			protect: Patterns::default(),
+			protect_strict: false,
+			block_suffixes: Suffixes::default(),
+			allow_suffixes: Suffixes::default(),
+			post_write: Vec::new(),
+			rejected_file: None,
+			report_path: None,
+			cnames: CnameTargets::default(),
+			rejected: Rejects::default(),
+			stats: RunStats::default(),
 		}
 	}
 }
@@ -91,13 +185,22 @@ impl Settings {
 	/// # Default Config Location.
 	pub(super) const DEFAULT_CONFIG: &str = "/etc/adbyss.toml";
 
-	/// # From File.
-	pub(super) fn from_file<P: AsRef<Path>>(src: P) -> Result<Self, AdbyssError> {
-		let src = src.as_ref();
-		let raw = std::fs::read_to_string(src)
-			.map_err(|_| AdbyssError::Read(src.to_string_lossy().into_owned()))?;
-		toml::from_str::<Self>(&raw)
-			.map_err(|e| AdbyssError::Parse(e.to_string()))
+	/// # From Config.
+	///
+	/// Build the settings from the merged config file(s).
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the merged config doesn't make sense.
+	pub(super) fn from_config(config: &Config) -> Result<Self, AdbyssError> {
+		let mut out = toml::Value::Table(config.table().clone())
+			.try_into::<Self>()
+			.map_err(|e| AdbyssError::Parse(e.to_string()))?;
+
+		// Excludes need the same www treatment as everything else.
+		out.exclude.expand_www(out.www_expansion);
+
+		Ok(out)
 	}
 }
 
@@ -105,12 +208,127 @@ impl Settings {
 	/// # Backup Original?
 	pub(super) const fn backup(&self) -> bool { self.backup }
 
+	/// # CNAME Targets.
+	///
+	/// Return the tracker targets of the CNAME-sourced hosts from the last
+	/// build.
+	pub(super) const fn cnames(&self) -> &CnameTargets { &self.cnames }
+
 	/// # Compact Output?
 	pub(super) const fn compact(&self) -> bool { self.compact }
 
 	/// # Needs Internet?
-	pub(super) const fn needs_internet(&self) -> bool {
-		self.source_adaway || self.source_stevenblack || self.source_yoyo
+	pub(super) fn needs_internet(&self) -> bool {
+		self.source_adaway ||
+		self.source_stevenblack ||
+		self.source_yoyo ||
+		self.cname_sources.iter().any(CnameSource::is_remote)
+	}
+
+	/// # Hostfile.
+	pub(super) fn hostfile(&self) -> &Path { &self.hostfile }
+
+	/// # Hostfile Paths.
+	///
+	/// Return the hostfile along with the backup and pause stash that live
+	/// beside it; i.e. everything a write, disable, pause, etc., might touch.
+	pub(super) fn hostfile_paths(&self) -> [PathBuf; 3] {
+		[
+			self.hostfile.clone(),
+			self.backup_path(),
+			crate::pause::stash_path(&self.hostfile),
+		]
+	}
+
+	/// # Blocked Suffixes.
+	///
+	/// Return the suffixes to block outright, minus any that are also
+	/// allowed.
+	///
+	/// Note: hostfiles can't express this; these only apply to the wildcard
+	/// `--format`s.
+	pub(super) fn block_suffixes(&self) -> Suffixes {
+		self.block_suffixes.without(&self.allow_suffixes)
+	}
+
+	/// # Regular Expressions.
+	///
+	/// Return the `regexclude` and `regexinclude` patterns, respectively.
+	pub(super) const fn regexes(&self) -> (&Regexes, &Regexes) {
+		(&self.regexclude, &self.regexinclude)
+	}
+
+	/// # Rejected Entries.
+	///
+	/// Return the entries rejected during the last build, along with the
+	/// path they should be saved to, if any.
+	pub(super) fn rejected(&self) -> (&Rejects, Option<&Path>) {
+		(&self.rejected, self.rejected_file.as_deref())
+	}
+
+	/// # Report Path.
+	pub(super) fn report_path(&self) -> Option<&Path> { self.report_path.as_deref() }
+
+	/// # Run Stats.
+	///
+	/// Return the particulars of the last build (and write), for the run
+	/// report.
+	pub(super) const fn stats(&self) -> &RunStats { &self.stats }
+
+	/// # Enabled Sources.
+	pub(super) fn sources(&self) -> Provenance {
+		let mut out = Provenance::default();
+		for (enabled, src) in [
+			(self.source_adaway, Source::AdAway),
+			(self.source_adbyss, Source::Adbyss),
+			(self.source_stevenblack, Source::StevenBlack),
+			(self.source_yoyo, Source::Yoyo),
+		] {
+			if enabled { out |= Provenance::from(src); }
+		}
+		if ! self.cname_sources.is_empty() { out |= Provenance::CNAME; }
+		out
+	}
+
+	/// # Config Hash.
+	///
+	/// Return a checksum of the settings that shape the blocklist, used to
+	/// tell whether or not the config has changed since the hostfile was last
+	/// written.
+	///
+	/// Settings that don't affect the output, like `backup` or `post_write`,
+	/// are left out so that changing them doesn't force a rewrite.
+	///
+	/// Note: this is derived from the `Debug` representations, so may change
+	/// between Adbyss releases too.
+	pub(super) fn config_hash(&self) -> u64 {
+		use std::{
+			fmt::Write,
+			hash::Hasher,
+		};
+
+		let mut out = Checksum::default();
+		let _res = write!(
+			out,
+			"{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+			self.compact,
+			self.source_adaway,
+			self.source_adbyss,
+			self.source_stevenblack,
+			self.source_yoyo,
+			self.categories,
+			self.cname_sources,
+			self.www_expansion,
+			self.exclude,
+			self.regexclude,
+			self.regexinclude,
+			self.include,
+			self.protect_defaults,
+			self.protect,
+			self.block_suffixes,
+			self.allow_suffixes,
+		);
+		out.finish()
 	}
 }
 
@@ -132,10 +350,12 @@ impl Settings {
 		let mut shitlist = self.shitlist()?;
 		shitlist.prune_custom_hosts(&out);
 
+		// (The final count is less any hosts the hostfile already defines.)
 		let len = shitlist.len();
+		self.stats.update(|s| s.unique = len);
 		if len != 0 {
-			shitlist.append(&mut out)
-				.map_err(|_| AdbyssError::Write(self.hostfile.to_string_lossy().into_owned()))?;
+			shitlist.append(&mut out, self.sources(), self.config_hash())
+				.map_err(|e| AdbyssError::Write(self.hostfile.to_string_lossy().into_owned(), e.to_string()))?;
 		}
 
 		Ok((out, len))
@@ -143,14 +363,22 @@ impl Settings {
 
 	/// # Write Changes!
 	///
-	/// Update the hostsfile and return the number of domains written.
+	/// Update the hostsfile and return a summary of what happened.
+	///
+	/// If the hostfile is already up-to-date, the write, backup, and hooks
+	/// are all skipped.
 	///
 	/// ## Errors
 	///
 	/// This will bubble up any errors encountered along the way.
-	pub(super) fn write(&self, yes: bool) -> Result<usize, AdbyssError> {
+	pub(super) fn write(&self, yes: bool) -> Result<WriteSummary<'_>, AdbyssError> {
 		let (out, len) = self.build()?;
 
+		// Nothing to do?
+		if std::fs::read_to_string(&self.hostfile).is_ok_and(|old| crate::write::same_hosts(&old, &out)) {
+			return Ok(WriteSummary { len, changed: false, hooks: Vec::new() });
+		}
+
 		// Double-check with the user before continuing.
 		if ! yes && ! fyi_msg::confirm!(@yes format!(
 			"Write {} hosts to {}?",
@@ -162,22 +390,140 @@ impl Settings {
 
 		// Backup and/or save.
 		self.try_backup()?;
-		crate::write::write_to_file(&self.hostfile, out.as_bytes()).map(|()| len)
+		crate::write::write_to_file(&self.hostfile, out.as_bytes())?;
+		self.stats.wrote("hostfile", &self.hostfile);
+
+		// Run the hooks, if any.
+		Ok(WriteSummary { len, changed: true, hooks: self.run_hooks() })
+	}
+
+	/// # Status.
+	///
+	/// Return the metadata from the hostfile's Adbyss section, if any.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if the hostfile cannot be read.
+	pub(super) fn status(&self) -> Result<Option<ShitlistMeta>, AdbyssError> {
+		crate::write::read_hosts(&self.hostfile).map(|(_, meta)| meta)
+	}
+
+	/// # Paused?
+	///
+	/// Return the pending pause, if any.
+	pub(super) fn paused(&self) -> Option<Pause> { Pause::load(&self.hostfile) }
+
+	/// # Pause.
+	///
+	/// Stash the Adbyss section and remove it from the hostfile for `secs`
+	/// seconds, returning the pause details along with the results of any
+	/// post-write hooks.
+	///
+	/// If Adbyss is already paused, only the deadline is changed.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if there is nothing to pause, or if any of
+	/// the reads or writes fail.
+	pub(super) fn pause(&self, secs: u32, yes: bool)
+	-> Result<(Pause, Vec<HookStatus<'_>>), AdbyssError> {
+		// Already paused? Just update the deadline.
+		if let Some(old) = self.paused() {
+			let pause = Pause::new(secs, old.block().to_owned());
+			pause.save(&self.hostfile)?;
+			return Ok((pause, Vec::new()));
+		}
+
+		let raw = std::fs::read_to_string(&self.hostfile)
+			.map_err(|e| AdbyssError::Read(self.hostfile.to_string_lossy().into_owned(), e.to_string()))?;
+		let block = crate::write::extract_block(&raw).ok_or(AdbyssError::Inactive)?;
+
+		// Double-check with the user before continuing.
+		if ! yes && ! fyi_msg::confirm!(@yes format!(
+			"Pause Adbyss blackholing in {}?",
+			self.hostfile.display(),
+		)) {
+			return Err(AdbyssError::Aborted);
+		}
+
+		// Stash first so nothing gets lost, then remove it.
+		let pause = Pause::new(secs, block.to_owned());
+		pause.save(&self.hostfile)?;
+		let (out, _) = crate::write::read_hosts(&self.hostfile)?;
+		self.try_backup()?;
+		crate::write::write_to_file(&self.hostfile, out.as_bytes())?;
+
+		Ok((pause, self.run_hooks()))
+	}
+
+	/// # Resume.
+	///
+	/// Restore the stashed Adbyss section to the hostfile, returning the
+	/// results of any post-write hooks, or `None` if there was nothing to
+	/// resume.
+	///
+	/// ## Errors
+	///
+	/// This will bubble up any read/write errors encountered along the way.
+	pub(super) fn resume(&self) -> Result<Option<Vec<HookStatus<'_>>>, AdbyssError> {
+		let Some(pause) = self.paused() else { return Ok(None); };
+
+		let (mut out, _) = crate::write::read_hosts(&self.hostfile)?;
+		out.push('\n');
+		out.push_str(pause.block());
+		crate::write::write_to_file(&self.hostfile, out.as_bytes())?;
+		Pause::remove(&self.hostfile);
+
+		Ok(Some(self.run_hooks()))
+	}
+
+	/// # Restore Backup.
+	///
+	/// Replace the hostfile with the backup saved by the last write, returning
+	/// the results of any post-write hooks.
+	///
+	/// ## Errors
+	///
+	/// This will return an error if there is no backup, or it cannot be
+	/// restored.
+	pub(super) fn restore(&self, yes: bool) -> Result<Vec<HookStatus<'_>>, AdbyssError> {
+		let src = self.backup_path();
+		let Ok(raw) = std::fs::read_to_string(&src) else {
+			return Err(AdbyssError::NoBackup(src.to_string_lossy().into_owned()));
+		};
+
+		// Prompt the user before taking any action.
+		if ! yes && ! fyi_msg::confirm!(@yes format!(
+			"Replace {} with {}?",
+			self.hostfile.display(),
+			src.display(),
+		)) {
+			return Err(AdbyssError::Aborted);
+		}
+
+		// A pending pause would undo the restoration; drop it.
+		Pause::remove(&self.hostfile);
+		crate::write::write_to_file(&self.hostfile, raw.as_bytes())?;
+		Ok(self.run_hooks())
 	}
 
 	/// # Unwrite Changes.
 	///
-	/// Remove Adbyss from the hosts file.
+	/// Remove Adbyss from the hosts file, returning the results of any
+	/// post-write hooks.
 	///
 	/// ## Errors
 	///
 	/// This will bubble up any errors encountered along the way.
-	pub(super) fn unwrite(&self, yes: bool) -> Result<(), AdbyssError> {
+	pub(super) fn unwrite(&self, yes: bool) -> Result<Vec<HookStatus<'_>>, AdbyssError> {
 		// Pull the current hosts file, stripped of any previous adbyss stuff.
-		let (out, changed) = crate::write::read_hosts(&self.hostfile)?;
+		let (out, meta) = crate::write::read_hosts(&self.hostfile)?;
+
+		// A pending pause would bring everything back; drop it.
+		Pause::remove(&self.hostfile);
 
 		// We only need to take action if there were entries to begin with.
-		if changed {
+		if meta.is_some() {
 			// Prompt the user before taking any action.
 			if ! yes && ! fyi_msg::confirm!(@yes format!(
 				"Remove all Adbyss blackhole entries from {}?",
@@ -188,9 +534,24 @@ impl Settings {
 
 			self.try_backup()?;
 			crate::write::write_to_file(&self.hostfile, out.as_bytes())?;
+			Ok(self.run_hooks())
 		}
+		else { Ok(Vec::new()) }
+	}
 
-		Ok(())
+	/// # Run Post-Write Hooks.
+	///
+	/// Run each of the user's post-write hooks, in order, returning their
+	/// statuses.
+	fn run_hooks(&self) -> Vec<HookStatus<'_>> {
+		self.post_write.iter().map(HookStatus::run).collect()
+	}
+
+	/// # Backup Path.
+	fn backup_path(&self) -> PathBuf {
+		let mut out = self.hostfile.clone();
+		out.as_mut_os_string().push(".adbyss.bak");
+		out
 	}
 
 	/// # Try Backup.
@@ -203,10 +564,10 @@ impl Settings {
 	/// This will return an error if the write fails.
 	fn try_backup(&self) -> Result<(), AdbyssError> {
 		if self.backup() && self.hostfile.is_file() {
-			let mut dst = self.hostfile.clone();
-			dst.as_mut_os_string().push(".adbyss.bak");
+			let dst = self.backup_path();
 			std::fs::copy(&self.hostfile, &dst)
-				.map_err(|_| AdbyssError::Write(dst.to_string_lossy().into_owned()))?;
+				.map_err(|e| AdbyssError::Write(dst.to_string_lossy().into_owned(), e.to_string()))?;
+			self.stats.wrote("backup", &dst);
 		}
 
 		Ok(())
@@ -224,77 +585,277 @@ impl Settings {
 	/// This will only return an error if there's a problem fetching the
 	/// source(s).
 	pub(super) fn shitlist(&self) -> Result<Shitlist, AdbyssError> {
+		let out: Vec<Domain> = self.entries()?
+			.into_iter()
+			.map(|(d, _)| d)
+			.collect();
+
+		// Done!
+		if self.compact() { Ok(Shitlist::Compact(out)) }
+		else { Ok(Shitlist::Flat(out)) }
+	}
+
+	/// # The Shitlist (With Provenance).
+	///
+	/// This does all the heavy lifting for [`Settings::shitlist`], but keeps
+	/// track of where each domain came from.
+	///
+	/// ## Errors
+	///
+	/// This will only return an error if there's a problem fetching the
+	/// source(s).
+	pub(super) fn entries(&self) -> Result<Vec<(Domain, Provenance)>, AdbyssError> {
+		let now = Instant::now();
+		self.stats.clear();
+		self.cnames.clear();
+		self.regexclude.reset_hits();
+		self.regexinclude.reset_hits();
 		let lists = self.download()?;
+		let mut cname = Provenance::CNAME;
+		cname |= Provenance::from(Category::Ads);
+		let cnames = self.download_cnames(cname)?;
 
 		// First, let's collect all domain-like string slices from the lists
-		// as there are likely to be a lot of repeats.
-		let mut raw: Vec<&str> = lists.iter()
-			.flat_map(|(source, list)| SourceDomains {
-				lines: list.lines(),
-				prefix: source.line_prefix(),
-				buf: None,
-			})
-			.chain(self.include.iter().map(String::as_str))
-			.collect();
-		raw.sort_unstable();
-		raw.dedup();
-
-		// With that out of the way, let's collect the _actual_ domains!
-		let mut out: Vec<Domain> = raw.into_iter()
-			.filter_map(|d| Domain::new(d).filter(|d| d.len() <= MAX_LINE))
-			.collect();
+		// as there are likely to be a lot of repeats. (The fetches were
+		// recorded in this same order, so we can note the per-list counts as
+		// we go.)
+		let mut raw: Vec<(&str, Provenance)> = Vec::new();
+		for (idx, (list, src)) in lists.iter().enumerate() {
+			let before = raw.len();
+			let prov = list.provenance();
+			raw.extend(
+				SourceDomains {
+					lines: src.lines(),
+					prefix: list.source().line_prefix(),
+				}.map(|d| (d, prov))
+			);
+			self.stats.set_parsed(idx, raw.len() - before);
+		}
+		for (idx, src) in cnames.iter().enumerate() {
+			let before = raw.len();
+			for (d, target) in CnamePairs::new(src) {
+				raw.push((d, cname));
+				if let Some(target) = target { self.cnames.insert(d, target); }
+			}
+			self.stats.set_parsed(lists.len() + idx, raw.len() - before);
+		}
+		raw.extend(self.include.hosts().iter().map(|d| (d.as_str(), Provenance::INCLUDE)));
+		raw.sort_unstable_by(|a, b| a.0.cmp(b.0));
+		raw.dedup_by(|a, b| merge_provenance(a.0 == b.0, a.1, &mut b.1));
+		let parsed = raw.len();
+
+		// With that out of the way, let's collect the _actual_ domains,
+		// making note of any rejects.
+		self.rejected.clear();
+		let mut out: Vec<(Domain, Provenance)> = Vec::with_capacity(raw.len());
+		for (d, p) in raw {
+			match Domain::new(d) {
+				Some(dom) if dom.len() <= MAX_LINE => { out.push((dom, p)); },
+				_ => { self.rejected.push(d, p); },
+			}
+		}
+		let rejected = self.rejected.len();
+
+		// Add the www/non-www counterparts, if any.
+		let mut expanded = 0;
+		if self.www_expansion != WwwExpansion::None {
+			let extra: Vec<(Domain, Provenance)> = out.iter()
+				.filter_map(|(d, p)|
+					self.www_expansion.expand(d)
+						.filter(|d| d.len() <= MAX_LINE)
+						.map(|alias| {
+							if p.contains(Provenance::CNAME) {
+								self.cnames.alias(d.as_str(), alias.as_str());
+							}
+							(alias, *p)
+						})
+				)
+				.collect();
+			expanded = extra.len();
+			out.extend(extra);
+		}
 
 		// Sort and dedupe again.
-		out.sort_unstable();
-		out.dedup();
-
-		// Apply the user's exclude rules, if any.
-		for ex in &self.exclude {
-			if let Ok(pos) = out.binary_search(ex) { out.remove(pos); }
+		out.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+		out.dedup_by(|a, b| merge_provenance(a.0 == b.0, a.1, &mut b.1));
+
+		// Apply the user's exclude rules, if any, sparing anything matching an
+		// include pattern. (The regular expressions are always checked so
+		// their hit counts are accurate.)
+		let before = out.len();
+		if
+			! self.exclude.is_empty() ||
+			! self.allow_suffixes.is_empty() ||
+			! self.regexclude.is_empty() ||
+			! self.regexinclude.is_empty()
+		{
+			out.retain(|(v, _)| {
+				let re_ex = self.regexclude.is_match(v.as_str());
+				let re_in = self.regexinclude.is_match(v.as_str());
+				re_in || self.include.keep(v) || ! (
+					re_ex ||
+					self.exclude.matches(v) ||
+					self.allow_suffixes.matches(v)
+				)
+			});
 		}
-		if let Some(re) = &self.regexclude { out.retain(|v| ! re.is_match(v.as_str())); }
+		self.stats.update(|s| {
+			s.parsed = parsed;
+			s.rejected = rejected;
+			s.expanded = expanded;
+			s.excluded = before - out.len();
+		});
+
+		// Make sure nothing essential has been caught up in the mix.
+		self.protect(&mut out)?;
 
 		// Done!
-		if self.compact() { Ok(Shitlist::Compact(out)) }
-		else { Ok(Shitlist::Flat(out)) }
+		self.stats.update(|s| {
+			s.unique = out.len();
+			s.build = now.elapsed();
+		});
+		Ok(out)
+	}
+
+	/// # Protect.
+	///
+	/// Remove any protected hosts — that weren't explicitly included — from
+	/// the list, recording each in the run stats so they can be reported.
+	///
+	/// ## Errors
+	///
+	/// In strict mode, an error is returned instead.
+	fn protect(&self, list: &mut Vec<(Domain, Provenance)>) -> Result<(), AdbyssError> {
+		let mut protected =
+			if self.protect_defaults { Patterns::protected() }
+			else { Patterns::default() };
+		protected.extend(&self.protect);
+		if protected.is_empty() { return Ok(()); }
+
+		let mut found = Vec::new();
+		list.retain(|(d, p)|
+			p.contains(Provenance::INCLUDE) || ! protected.matches(d) || {
+				found.push(d.as_str().to_owned());
+				false
+			}
+		);
+
+		if found.is_empty() { Ok(()) }
+		else if self.protect_strict { Err(AdbyssError::Protected(found.join(", "))) }
+		else {
+			self.stats.update(|s| { s.protected = found; });
+			Ok(())
+		}
+	}
+
+	/// # Download CNAME Lists.
+	///
+	/// Read or download each of the CNAME sources, recording the fetches
+	/// under the given provenance.
+	fn download_cnames(&self, prov: Provenance) -> Result<Vec<String>, AdbyssError> {
+		self.cname_sources.iter()
+			.map(|src| {
+				let now = Instant::now();
+				let res = src.fetch_raw();
+				let origin = if src.is_remote() { Origin::Download } else { Origin::File };
+				self.stats.push_fetch(Fetch::new(
+					"cname",
+					prov,
+					src.to_string(),
+					res.as_ref().map(|raw| (origin, raw.len())),
+					now.elapsed(),
+				));
+				res
+			})
+			.collect()
 	}
 
 	/// # Download.
 	///
-	/// Download (or pull from cache) all enabled source lists.
-	fn download(&self) -> Result<[(Source, Cow<'static, str>); 4], AdbyssError> {
-		let mut out = [
-			(Source::AdAway, Cow::Borrowed("")),
-			(Source::Adbyss, Cow::Borrowed("")),
-			(Source::StevenBlack, Cow::Borrowed("")),
-			(Source::Yoyo, Cow::Borrowed("")),
-		];
+	/// Download (or pull from cache) all enabled source lists covering any of
+	/// the enabled categories.
+	fn download(&self) -> Result<Vec<(List, Cow<'static, str>)>, AdbyssError> {
+		let categories = self.categories.mask();
+		let lists: Vec<List> = [
+			(self.source_adaway, Source::AdAway),
+			(self.source_adbyss, Source::Adbyss),
+			(self.source_stevenblack, Source::StevenBlack),
+			(self.source_yoyo, Source::Yoyo),
+		]
+			.into_iter()
+			.filter_map(|(enabled, src)| enabled.then_some(src))
+			.flat_map(|src| src.lists(categories))
+			.collect();
 
 		std::thread::scope(|s| {
 			// Network I/O drags; let's parallelize our efforts!
-			let workers = [
-				(if self.source_adaway { Some(s.spawn(|| Source::AdAway.fetch_raw())) } else { None }),
-				(if self.source_adbyss { Some(s.spawn(|| Source::Adbyss.fetch_raw())) } else { None }),
-				(if self.source_stevenblack { Some(s.spawn(|| Source::StevenBlack.fetch_raw())) } else { None }),
-				(if self.source_yoyo { Some(s.spawn(|| Source::Yoyo.fetch_raw())) } else { None }),
-			];
-
-			// Pull in the results.
-			for (mut thread, (source, raw)) in workers.into_iter().zip(out.iter_mut()) {
-				let Some(thread) = thread.take() else { continue; };
-				let thread = thread.join()
-					.map_err(|_| AdbyssError::SourceFetch(*source))?;
-				*raw = thread?;
+			let workers: Vec<_> = lists.into_iter()
+				.map(|list| (list, s.spawn(move || {
+					let now = Instant::now();
+					(list.fetch_raw(), now.elapsed())
+				})))
+				.collect();
+
+			// Pull in (and record) the results, holding off on any errors
+			// until everything's been accounted for.
+			let mut out = Vec::with_capacity(workers.len());
+			let mut err = None;
+			for (list, thread) in workers {
+				let (res, elapsed) = thread.join().unwrap_or_else(|_| (
+					Err(AdbyssError::SourceFetch(
+						list.source().as_str().to_owned(),
+						"thread panicked".to_owned(),
+					)),
+					Duration::ZERO,
+				));
+				self.stats.push_fetch(Fetch::new(
+					list.source().key(),
+					list.provenance(),
+					list.url().to_owned(),
+					res.as_ref().map(|(raw, origin)| (*origin, raw.len())),
+					elapsed,
+				));
+				match res {
+					Ok((raw, _)) => { out.push((list, raw)); },
+					Err(e) => if err.is_none() { err.replace(e); },
+				}
 			}
 
 			// Done!
-			Ok(out)
+			err.map_or(Ok(out), Err)
 		})
 	}
 }
 
 
 
+/// # Write Summary.
+///
+/// This holds the results of a [`Settings::write`] call.
+pub(super) struct WriteSummary<'a> {
+	/// # Number of Hosts Blocked.
+	len: usize,
+
+	/// # Hostfile Changed?
+	changed: bool,
+
+	/// # Post-Write Hook Results.
+	hooks: Vec<HookStatus<'a>>,
+}
+
+impl WriteSummary<'_> {
+	/// # Number of Hosts Blocked.
+	pub(super) const fn len(&self) -> usize { self.len }
+
+	/// # Hostfile Changed?
+	pub(super) const fn changed(&self) -> bool { self.changed }
+
+	/// # Post-Write Hook Results.
+	pub(super) fn hooks(&self) -> &[HookStatus<'_>] { &self.hooks }
+}
+
+
+
 /// # Source Domains Iter.
 ///
 /// Tease domain-like strings out of a raw source list.
@@ -304,9 +865,6 @@ struct SourceDomains<'a> {
 
 	/// # Leading IP to strip, if any.
 	prefix: &'static str,
-
-	/// # Non-www Buffer.
-	buf: Option<&'a str>,
 }
 
 impl<'a> Iterator for SourceDomains<'a> {
@@ -314,9 +872,6 @@ impl<'a> Iterator for SourceDomains<'a> {
 
 	fn next(&mut self) -> Option<Self::Item> {
 		loop {
-			// Prioritize the buffer.
-			if let Some(last) = self.buf.take() { return Some(last); }
-
 			// Trim the line.
 			let mut line = self.lines.next()?.trim();
 			if line.is_empty() { continue; }
@@ -330,20 +885,13 @@ impl<'a> Iterator for SourceDomains<'a> {
 			}
 
 			// If anything remains, return it!
-			if ! line.is_empty() {
-				// If www is banned, non-www should be too.
-				if let Some(rest) = line.strip_prefix("www.") {
-					self.buf.replace(rest);
-				}
-
-				return Some(line);
-			}
+			if ! line.is_empty() { return Some(line); }
 		}
 	}
 
 	fn size_hint(&self) -> (usize, Option<usize>) {
 		let (_, hint) = self.lines.size_hint();
-		(usize::from(self.buf.is_some()), hint)
+		(0, hint)
 	}
 }
 
@@ -351,43 +899,14 @@ impl std::iter::FusedIterator for SourceDomains<'_> {}
 
 
 
-#[expect(clippy::unnecessary_wraps, reason = "We don't control the signature.")]
-#[expect(clippy::option_if_let_else, reason = "Too messy.")]
-/// # Deserialize Include.
-fn deserialize_include<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
-where D: de::Deserializer<'de> {
-	match Vec::<String>::deserialize(deserializer) {
-		Ok(mut tmp) => {
-			// Make sure the entries are lowercase, and if there are www-prefixed
-			// domains, add their non-www counterparts.
-			let mut extra = Vec::with_capacity(tmp.len());
-			for v in &mut tmp {
-				v.make_ascii_lowercase();
-				if let Some(rest) = v.strip_prefix("www.") { extra.push(rest.to_owned()); }
-			}
-			tmp.append(&mut extra);
-
-			// Sort and dedupe.
-			tmp.sort_unstable();
-			tmp.dedup();
-
-			Ok(tmp)
-		},
-		_ => Ok(Vec::new()),
-	}
-}
-
-/// # Deserialize Regexclude.
-fn deserialize_regexclude<'de, D>(deserializer: D) -> Result<Option<RegexSet>, D::Error>
-where D: de::Deserializer<'de> {
-	match Vec::<Cow<str>>::deserialize(deserializer) {
-		Ok(tmp) => {
-			let tmp = RegexSet::new(tmp).map_err(de::Error::custom)?;
-			if tmp.is_empty() { Ok(None) }
-			else { Ok(Some(tmp)) }
-		},
-		_ => Ok(None),
-	}
+#[inline]
+/// # Merge Provenance.
+///
+/// This is a `dedup_by` helper: if the entries are duplicates, the provenance
+/// of the one being removed is merged into the one being kept.
+fn merge_provenance(dupe: bool, src: Provenance, dst: &mut Provenance) -> bool {
+	if dupe { *dst |= src; }
+	dupe
 }
 
 /// # Snip Domain Line.
@@ -408,9 +927,35 @@ fn snip_domain_line<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
 mod tests {
 	use super::*;
 
+	#[test]
+	fn t_config_hash() {
+		let config = Config::load(Some(Path::new("skel/test.toml")), None)
+			.expect("Unable to load config.");
+		let mut settings = Settings::from_config(&config)
+			.expect("Unable to parse settings.");
+		let hash = settings.config_hash();
+
+		// Settings that don't affect the output shouldn't affect the hash.
+		settings.backup = ! settings.backup;
+		settings.hostfile = PathBuf::from("/tmp/hosts");
+		settings.post_write = toml::from_str::<Settings>(r#"post_write = ["echo other"]"#)
+			.expect("Unable to parse settings.")
+			.post_write;
+		settings.protect_strict = ! settings.protect_strict;
+		settings.rejected_file = Some(PathBuf::from("/tmp/rejects.tsv"));
+		settings.report_path = Some(PathBuf::from("/tmp/report.json"));
+		assert_eq!(hash, settings.config_hash());
+
+		// But those that do, should.
+		settings.compact = ! settings.compact;
+		assert_ne!(hash, settings.config_hash());
+	}
+
 	#[test]
 	fn t_filters() {
-		let settings = Settings::from_file("skel/test.toml")
+		let config = Config::load(Some(Path::new("skel/test.toml")), None)
+			.expect("Unable to load config.");
+		let settings = Settings::from_config(&config)
 			.expect("Unable to parse settings.");
 
 		// The only enabled source should be our own (local) one.
@@ -434,8 +979,92 @@ mod tests {
 		// And our manual excludes are not.
 		assert!(! res.contains(&String::from("collect.snitcher.com")));
 		assert!(! res.contains(&String::from("triptease.io")));
+		assert!(! res.contains(&String::from("trendmd.com")));
+		assert!(! res.contains(&String::from("js.trendmd.com")));
+
+		// Unless re-included.
+		assert!(res.contains(&String::from("api.triptease.io")));
+		assert_eq!(
+			settings.regexclude.stats().collect::<Vec<_>>(),
+			[(r"(.+\.)*triptease.io$", 7)],
+		);
+		assert_eq!(
+			settings.regexinclude.stats().collect::<Vec<_>>(),
+			[(r"^api\.triptease\.io$", 1)],
+		);
+
+		// Rebuilding shouldn't double-count.
+		let _res = settings.shitlist().expect("Shitlist failed!");
+		assert_eq!(
+			settings.regexclude.stats().collect::<Vec<_>>(),
+			[(r"(.+\.)*triptease.io$", 7)],
+		);
+
+		// Include patterns should be counted even without excludes.
+		let mut settings = settings;
+		settings.exclude = Patterns::default();
+		settings.allow_suffixes = Suffixes::default();
+		settings.regexclude = Regexes::default();
+		let _res = settings.shitlist().expect("Shitlist failed!");
+		assert_eq!(
+			settings.regexinclude.stats().collect::<Vec<_>>(),
+			[(r"^api\.triptease\.io$", 1)],
+		);
 
 		// Double check at least one of Adbyss' other entries is present.
 		assert!(res.contains(&String::from("www.snitcher.com")));
+
+		// Non-www counterparts should be there, but not www ones.
+		assert!(res.contains(&String::from("robin.com")));
+		assert!(res.contains(&String::from("www.robin.com")));
+		assert!(! res.contains(&String::from("www.batman.com")));
+
+		// The CNAME hosts should be there.
+		assert!(res.contains(&String::from("metrics.blobfolio.com")));
+		assert!(res.contains(&String::from("smetrics.example.co.uk")));
+		assert!(settings.sources().contains(Provenance::CNAME));
+		assert!(settings.entries().expect("Shitlist failed!").iter().any(|(d, p)|
+			d.as_str() == "a8.example.com" && p.contains(Provenance::CNAME)
+		));
+
+		// Except the bad one.
+		assert!(! res.contains(&String::from("bad_host.example.org")));
+		assert_eq!(settings.rejected.summary(), ["CNAME: 1 (1 invalid label)"]);
+		assert_eq!(
+			settings.cnames().get("a8.example.com").as_deref(),
+			Some("example.eulerian.net"),
+		);
+
+		// Protected hosts should be gone.
+		assert!(! res.contains(&String::from("snid.snitcher.com")));
+		assert_eq!(settings.stats().protected(), ["snid.snitcher.com"]);
+
+		// Try the www alternatives.
+		let mut settings = settings;
+		settings.www_expansion = WwwExpansion::None;
+		let res = settings.entries().expect("Shitlist failed!");
+		assert!(! res.iter().any(|(d, _)| d.as_str() == "robin.com"));
+
+		settings.www_expansion = WwwExpansion::Both;
+		let res = settings.entries().expect("Shitlist failed!");
+		assert!(res.iter().any(|(d, _)| d.as_str() == "robin.com"));
+		assert!(res.iter().any(|(d, _)| d.as_str() == "www.batman.com"));
+
+		// Disabling the ads category should knock out the Adbyss list.
+		settings.www_expansion = WwwExpansion::Strip;
+		settings.categories = toml::from_str("ads = false").expect("Unable to parse categories.");
+		let res = settings.entries().expect("Shitlist failed!");
+		assert!(! res.iter().any(|(d, _)| d.as_str() == "www.snitcher.com"));
+		assert!(res.iter().any(|(d, p)|
+			d.as_str() == "batman.com" && p.contains(Provenance::INCLUDE)
+		));
+		settings.categories = Categories::default();
+
+		// Or fatal, in strict mode.
+		settings.protect_strict = true;
+		assert!(matches!(
+			settings.entries(),
+			Err(AdbyssError::Protected(s)) if s == "snid.snitcher.com",
+		));
 	}
 }
@@ -3,6 +3,7 @@
 */
 
 use crate::AdbyssError;
+use serde::Deserialize;
 use std::{
 	borrow::Cow,
 	fs::File,
@@ -42,40 +43,159 @@ impl Source {
 			Self::Yoyo => "Yoyo",
 		}
 	}
+
+	/// # Config Key.
+	///
+	/// The name used for the source in the config (`source_<key>`) and the
+	/// `--source` option.
+	pub(super) const fn key(self) -> &'static str {
+		match self {
+			Self::AdAway => "adaway",
+			Self::Adbyss => "adbyss",
+			Self::StevenBlack => "stevenblack",
+			Self::Yoyo => "yoyo",
+		}
+	}
+}
+
+/// # Constants.
+impl Source {
+	/// # All Sources.
+	pub(super) const ALL: [Self; 4] = [
+		Self::AdAway,
+		Self::Adbyss,
+		Self::StevenBlack,
+		Self::Yoyo,
+	];
+
+	/// # All Lists.
+	const LISTS: [List; 8] = [
+		List {
+			source: Self::AdAway,
+			categories: Category::Ads as u8,
+			url: "https://adaway.org/hosts.txt",
+			cache: "_adbyss-adaway.tmp",
+		},
+		List {
+			source: Self::Adbyss,
+			categories: Category::Ads as u8,
+			url: "",
+			cache: "",
+		},
+		List {
+			source: Self::StevenBlack,
+			categories: Category::Ads as u8 | Category::Malware as u8,
+			url: "https://raw.githubusercontent.com/StevenBlack/hosts/master/hosts",
+			cache: "_adbyss-sb.tmp",
+		},
+		List {
+			source: Self::StevenBlack,
+			categories: Category::FakeNews as u8,
+			url: "https://raw.githubusercontent.com/StevenBlack/hosts/master/alternates/fakenews-only/hosts",
+			cache: "_adbyss-sb-fakenews.tmp",
+		},
+		List {
+			source: Self::StevenBlack,
+			categories: Category::Gambling as u8,
+			url: "https://raw.githubusercontent.com/StevenBlack/hosts/master/alternates/gambling-only/hosts",
+			cache: "_adbyss-sb-gambling.tmp",
+		},
+		List {
+			source: Self::StevenBlack,
+			categories: Category::Porn as u8,
+			url: "https://raw.githubusercontent.com/StevenBlack/hosts/master/alternates/porn-only/hosts",
+			cache: "_adbyss-sb-porn.tmp",
+		},
+		List {
+			source: Self::StevenBlack,
+			categories: Category::Social as u8,
+			url: "https://raw.githubusercontent.com/StevenBlack/hosts/master/alternates/social-only/hosts",
+			cache: "_adbyss-sb-social.tmp",
+		},
+		List {
+			source: Self::Yoyo,
+			categories: Category::Ads as u8,
+			url: "https://pgl.yoyo.org/adservers/serverlist.php?hostformat=hosts&showintro=0&mimetype=plaintext",
+			cache: "_adbyss-yoyo.tmp",
+		},
+	];
 }
 
 /// # Getters.
 impl Source {
-	/// # Cache path.
-	fn cache_path(self) -> PathBuf {
-		std::env::temp_dir().join(
-			match self {
-				Self::AdAway => "_adbyss-adaway.tmp",
-				Self::Adbyss => "_adbyss.tmp",
-				Self::StevenBlack => "_adbyss-sb.tmp",
-				Self::Yoyo => "_adbyss-yoyo.tmp",
-			}
-		)
+	/// # Lists.
+	///
+	/// Return the source's lists covering any of the given categories.
+	pub(super) fn lists(self, categories: u8) -> impl Iterator<Item=List> {
+		Self::LISTS.into_iter()
+			.filter(move |l| l.source == self && 0 != l.categories & categories)
+	}
+
+	/// # Line Prefix.
+	pub(super) const fn line_prefix(self) -> &'static str {
+		match self {
+			Self::AdAway | Self::Yoyo => "127.0.0.1 ",
+			Self::StevenBlack =>  "0.0.0.0 ",
+			Self::Adbyss => "",
+		}
 	}
+}
+
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// # Source List.
+///
+/// A single list belonging to a [`Source`], along with the categories its
+/// entries fall under.
+pub(super) struct List {
+	/// # Source.
+	source: Source,
+
+	/// # Categories.
+	categories: u8,
+
+	/// # URL.
+	url: &'static str,
+
+	/// # Cache File Name.
+	cache: &'static str,
+}
+
+impl List {
+	/// # Source.
+	pub(super) const fn source(self) -> Source { self.source }
+
+	/// # Provenance.
+	pub(super) const fn provenance(self) -> Provenance {
+		Provenance { sources: self.source as u8, categories: self.categories }
+	}
+
+	/// # URL.
+	///
+	/// This is empty for Adbyss' own (built-in) list.
+	pub(super) const fn url(self) -> &'static str { self.url }
 
 	/// # Fetch Raw Source Data.
 	///
+	/// Return the data, along with where it came from.
+	///
 	/// ## Errors
 	///
 	/// This returns an error if the data cannot be downloaded or parsed.
-	pub(super) fn fetch_raw(self) -> Result<Cow<'static, str>, AdbyssError> {
+	pub(super) fn fetch_raw(self) -> Result<(Cow<'static, str>, Origin), AdbyssError> {
 		use std::io::Write;
 
 		// Adbyss' own dataset is static.
-		if matches!(self, Self::Adbyss) {
-			return Ok(Cow::Borrowed(include_str!("../skel/adbyss.txt")));
+		if matches!(self.source, Source::Adbyss) {
+			return Ok((Cow::Borrowed(include_str!("../skel/adbyss.txt")), Origin::Builtin));
 		}
 
 		// Check the cache first. If the source was downloaded less than an
 		// hour ago, we can use that instead of asking the Internet for a new
 		// copy.
 		let cache = self.cache_path();
-		if let Some(out) = read_from_cache(&cache) { return Ok(Cow::Owned(out)); }
+		if let Some(out) = read_from_cache(&cache) { return Ok((Cow::Owned(out), Origin::Cache)); }
 
 		// Try to download it.
 		let out = download_source(self)?;
@@ -87,50 +207,257 @@ impl Source {
 		);
 
 		// Return it!
-		Ok(Cow::Owned(out))
+		Ok((Cow::Owned(out), Origin::Download))
 	}
 
-	/// # Line Prefix.
-	pub(super) const fn line_prefix(self) -> &'static str {
+	/// # Cache path.
+	fn cache_path(self) -> PathBuf { std::env::temp_dir().join(self.cache) }
+}
+
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// # Fetch Origin.
+///
+/// Where a list's data actually came from.
+pub(super) enum Origin {
+	/// # Built In.
+	Builtin,
+
+	/// # Cache (Hit).
+	Cache,
+
+	/// # Download (Cache Miss).
+	Download,
+
+	/// # Local File.
+	File,
+}
+
+impl Origin {
+	/// # As Str.
+	pub(super) const fn as_str(self) -> &'static str {
 		match self {
-			Self::AdAway | Self::Yoyo => "127.0.0.1 ",
-			Self::StevenBlack =>  "0.0.0.0 ",
-			Self::Adbyss => "",
+			Self::Builtin => "builtin",
+			Self::Cache => "cache",
+			Self::Download => "download",
+			Self::File => "file",
 		}
 	}
 
-	/// # Source URL.
+	/// # Cache Status.
 	///
-	/// For remote hosts, return the URL where data is found.
-	const fn url(self) -> &'static str {
+	/// Return `hit` or `miss` for remote lists, or `None` for the rest.
+	pub(super) const fn cache(self) -> Option<&'static str> {
 		match self {
-			Self::AdAway => "https://adaway.org/hosts.txt",
-			Self::Adbyss => "",
-			Self::StevenBlack => "https://raw.githubusercontent.com/StevenBlack/hosts/master/hosts",
-			Self::Yoyo => "https://pgl.yoyo.org/adservers/serverlist.php?hostformat=hosts&showintro=0&mimetype=plaintext",
+			Self::Cache => Some("hit"),
+			Self::Download => Some("miss"),
+			Self::Builtin | Self::File => None,
+		}
+	}
+}
+
+
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// # Category.
+pub(super) enum Category {
+	/// # Ads and Trackers.
+	Ads =      0b0000_0001_u8,
+
+	/// # Malware.
+	Malware =  0b0000_0010_u8,
+
+	/// # Fake News.
+	FakeNews = 0b0000_0100_u8,
+
+	/// # Gambling.
+	Gambling = 0b0000_1000_u8,
+
+	/// # Porn.
+	Porn =     0b0001_0000_u8,
+
+	/// # Social Media.
+	Social =   0b0010_0000_u8,
+}
+
+impl Category {
+	/// # All Categories.
+	pub(super) const ALL: [Self; 6] = [
+		Self::Ads,
+		Self::Malware,
+		Self::FakeNews,
+		Self::Gambling,
+		Self::Porn,
+		Self::Social,
+	];
+
+	/// # As Str.
+	pub(super) const fn as_str(self) -> &'static str {
+		match self {
+			Self::Ads => "ads",
+			Self::Malware => "malware",
+			Self::FakeNews => "fakenews",
+			Self::Gambling => "gambling",
+			Self::Porn => "porn",
+			Self::Social => "social",
+		}
+	}
+}
+
+
+
+#[expect(clippy::struct_excessive_bools, reason = "The fields mirror our TOML config.")]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// # Category Toggles.
+///
+/// This holds the user's `[categories]` preferences. Lists are only used if
+/// one or more of their categories is enabled.
+pub(super) struct Categories {
+	/// # Ads and Trackers.
+	ads: bool,
+
+	/// # Malware.
+	malware: bool,
+
+	/// # Fake News.
+	fakenews: bool,
+
+	/// # Gambling.
+	gambling: bool,
+
+	/// # Porn.
+	porn: bool,
+
+	/// # Social Media.
+	social: bool,
+}
+
+impl Default for Categories {
+	fn default() -> Self {
+		Self {
+			ads: true,
+			malware: true,
+			fakenews: false,
+			gambling: false,
+			porn: false,
+			social: false,
 		}
 	}
 }
 
+impl Categories {
+	/// # As Bitmask.
+	pub(super) const fn mask(self) -> u8 {
+		let mut out = 0;
+		if self.ads { out |= Category::Ads as u8; }
+		if self.malware { out |= Category::Malware as u8; }
+		if self.fakenews { out |= Category::FakeNews as u8; }
+		if self.gambling { out |= Category::Gambling as u8; }
+		if self.porn { out |= Category::Porn as u8; }
+		if self.social { out |= Category::Social as u8; }
+		out
+	}
+}
+
+
 
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+/// # Provenance.
+///
+/// This is a simple pair of bitmasks recording which [`Source`](s) — and/or
+/// the user's own `cname_sources` and `include` lists — a given host was
+/// found in, and under which [`Category`](s).
+pub(super) struct Provenance {
+	/// # Sources.
+	sources: u8,
+
+	/// # Categories.
+	categories: u8,
+}
+
+impl std::ops::BitOrAssign for Provenance {
+	#[inline]
+	fn bitor_assign(&mut self, rhs: Self) {
+		self.sources |= rhs.sources;
+		self.categories |= rhs.categories;
+	}
+}
+
+impl From<Source> for Provenance {
+	#[inline]
+	fn from(src: Source) -> Self { Self { sources: src as u8, categories: 0 } }
+}
+
+impl From<Category> for Provenance {
+	#[inline]
+	fn from(src: Category) -> Self { Self { sources: 0, categories: src as u8 } }
+}
+
+impl Provenance {
+	/// # CNAME Source(s).
+	pub(super) const CNAME: Self = Self { sources: 0b0100_0000, categories: 0 };
+
+	/// # User Include.
+	pub(super) const INCLUDE: Self = Self { sources: 0b1000_0000, categories: 0 };
+
+	/// # Contains?
+	///
+	/// Returns `true` if all of the bits in `other` are also in `self`.
+	pub(super) const fn contains(self, other: Self) -> bool {
+		(other.sources != 0 || other.categories != 0) &&
+		self.sources & other.sources == other.sources &&
+		self.categories & other.categories == other.categories
+	}
+
+	/// # Iterate Names.
+	///
+	/// Return an iterator over the names of each source in the set, in order.
+	pub(super) fn names(self) -> impl Iterator<Item=&'static str> {
+		Source::ALL.into_iter()
+			.filter_map(move |s| if 0 == self.sources & s as u8 { None } else { Some(s.as_str()) })
+			.chain((0 != self.sources & Self::CNAME.sources).then_some("CNAME"))
+			.chain((0 != self.sources & Self::INCLUDE.sources).then_some("Include"))
+	}
+
+	/// # Iterate Categories.
+	///
+	/// Return an iterator over the names of each category in the set, in
+	/// order.
+	pub(super) fn categories(self) -> impl Iterator<Item=&'static str> {
+		Category::ALL.into_iter()
+			.filter_map(move |c| if 0 == self.categories & c as u8 { None } else { Some(c.as_str()) })
+	}
+}
+
+
+
+/// # Download.
+///
+/// Fetch the remote URL as a string. On failure, the reason — e.g. the HTTP
+/// status — is returned instead.
+pub(super) fn download(url: &str) -> Result<String, String> {
+	let res = minreq::get(url)
+		.with_header("user-agent", "Mozilla/5.0")
+		.with_timeout(15)
+		.send()
+		.map_err(|e| e.to_string())?;
+
+	if ! (200..=399).contains(&res.status_code) {
+		return Err(format!("HTTP {} {}", res.status_code, res.reason_phrase));
+	}
+
+	res.as_str().map(str::to_owned).map_err(|e| e.to_string())
+}
 
 /// # Download Source.
 ///
-/// This will try to fetch the remote source data, using Gzip encoding where
-/// possible to reduce the transfer times. All sources currently serve Gzipped
-/// content, so the extra complexity is worth it.
-fn download_source(kind: Source) -> Result<String, AdbyssError> {
-	if
-		let Ok(res) = minreq::get(kind.url())
-			.with_header("user-agent", "Mozilla/5.0")
-			.with_timeout(15)
-			.send() &&
-		(200..=399).contains(&res.status_code) &&
-		let Ok(out) = res.as_str()
-	{
-		Ok(out.to_owned())
-	}
-	else { Err(AdbyssError::SourceFetch(kind)) }
+/// This will try to fetch the remote source data.
+fn download_source(list: List) -> Result<String, AdbyssError> {
+	download(list.url)
+		.map_err(|e| AdbyssError::SourceFetch(list.source.as_str().to_owned(), e))
 }
 
 /// # Read From Cache.
@@ -148,3 +475,44 @@ fn read_from_cache(src: &Path) -> Option<String> {
 	// Nope.
 	None
 }
+
+
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn t_lists() {
+		let all = Category::ALL.iter().fold(0, |acc, c| acc | *c as u8);
+		for src in Source::ALL {
+			assert!(src.lists(all).count() != 0, "{} has no lists.", src.as_str());
+		}
+
+		// The default categories should only pull the base lists.
+		let mask = Categories::default().mask();
+		assert_eq!(Source::StevenBlack.lists(mask).count(), 1);
+		assert_eq!(Source::StevenBlack.lists(all).count(), 5);
+		assert_eq!(Source::StevenBlack.lists(Category::Porn as u8).count(), 1);
+		assert_eq!(Source::Yoyo.lists(Category::Porn as u8).count(), 0);
+	}
+
+	#[test]
+	fn t_provenance() {
+		let mut prov = List {
+			source: Source::StevenBlack,
+			categories: Category::Ads as u8 | Category::Malware as u8,
+			url: "",
+			cache: "",
+		}.provenance();
+		prov |= Provenance::from(Category::Gambling);
+		prov |= Provenance::INCLUDE;
+
+		assert_eq!(prov.names().collect::<Vec<_>>(), ["Steven Black", "Include"]);
+		assert_eq!(prov.categories().collect::<Vec<_>>(), ["ads", "malware", "gambling"]);
+		assert!(prov.contains(Provenance::INCLUDE));
+		assert!(prov.contains(Provenance::from(Category::Malware)));
+		assert!(! prov.contains(Provenance::CNAME));
+		assert!(! prov.contains(Provenance::default()));
+	}
+}
@@ -6,16 +6,24 @@ use adbyss_psl::Domain;
 use crate::{
 	AdbyssError,
 	MAX_LINE,
+	source::Provenance,
 };
 use dactyl::NiceU64;
 use std::{
-	collections::BTreeMap,
+	collections::{
+		BTreeMap,
+		BTreeSet,
+	},
 	fmt,
+	hash::Hasher,
 	net::IpAddr,
 	path::Path,
 };
 use trimothy::TrimMut;
-use utc2k::FmtUtc2k;
+use utc2k::{
+	FmtUtc2k,
+	Utc2k,
+};
 
 
 
@@ -25,6 +33,24 @@ const MARKER_START: &str = "##########\n# ADBYSS #\n##########";
 /// # End Marker.
 const MARKER_END: &str = "## End of Adbyss Rules ##\n";
 
+/// # Header Timestamp Prefix.
+const HEADER_UPDATED: &str = "# Updated: ";
+
+/// # Header Count Prefix.
+const HEADER_BLOCKED: &str = "# Blocked: ";
+
+/// # Header Version Prefix.
+const HEADER_VERSION: &str = "# Version: ";
+
+/// # Header Sources Prefix.
+const HEADER_SOURCES: &str = "# Sources: ";
+
+/// # Header Config Hash Prefix.
+const HEADER_CONFIG: &str = "# Config: ";
+
+/// # Header Checksum Prefix.
+const HEADER_CHECKSUM: &str = "# Checksum: ";
+
 
 
 /// # Shitlist.
@@ -37,6 +63,7 @@ pub(super) enum Shitlist {
 }
 
 impl Shitlist {
+	#[cfg(test)]
 	/// # Into Vec.
 	///
 	/// Return the inner domain list. Note this is always "flat".
@@ -93,10 +120,23 @@ impl Shitlist {
 	///
 	/// Append the Adbyss section header, shitlist, and footer to the end of
 	/// the hosts string.
-	pub(super) fn append(&self, hosts: &mut String) -> Result<(), fmt::Error> {
+	///
+	/// The enabled `sources` and `config` hash are recorded in the header
+	/// alongside a checksum of the entries so they can be read back later.
+	pub(super) fn append(&self, hosts: &mut String, sources: Provenance, config: u64)
+	-> Result<(), fmt::Error> {
 		use fmt::Write;
+
+		let body = self.to_string();
+		let header = ShitlistHeader {
+			len: self.len(),
+			sources,
+			config,
+			checksum: Checksum::body(&body),
+		};
+
 		hosts.try_reserve(self.estimate_byte_len()).map_err(|_| fmt::Error)?;
-		write!(hosts, "\n{}\n{self}\n{MARKER_END}", ShitlistHeader(self.len()))
+		write!(hosts, "\n{header}\n{body}\n{MARKER_END}")
 	}
 }
 
@@ -146,14 +186,26 @@ impl fmt::Display for Shitlist {
 ///
 /// This is used to print a pretty marker/header to identify the list. The
 /// footer, by contrast, is just a single line, so `MARKER_END` is sufficient.
-struct ShitlistHeader(usize);
+struct ShitlistHeader {
+	/// # Number of Entries.
+	len: usize,
+
+	/// # Enabled Sources.
+	sources: Provenance,
+
+	/// # Config Hash.
+	config: u64,
+
+	/// # Entry Checksum.
+	checksum: u64,
+}
 
 impl fmt::Display for ShitlistHeader {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		// No notes for no output.
-		if self.0 == 0 { writeln!(f, "{MARKER_START}") }
+		if self.len == 0 { writeln!(f, "{MARKER_START}") }
 		else {
-			let len = NiceU64::from(self.0);
+			let len = NiceU64::from(self.len);
 			let now = FmtUtc2k::now();
 			writeln!(f, "{MARKER_START}
 #
@@ -162,44 +214,202 @@ impl fmt::Display for ShitlistHeader {
 #
 # If you have custom host entries, add them before or after this section.
 #
-# Updated: {now} UTC
-# Blocked: {len} garbage hosts
+{HEADER_UPDATED}{now} UTC
+{HEADER_BLOCKED}{len} garbage hosts
+#
+{HEADER_VERSION}{}", env!("CARGO_PKG_VERSION"))?;
+
+			f.write_str(HEADER_SOURCES)?;
+			for (k, name) in self.sources.names().enumerate() {
+				if k != 0 { f.write_str(", ")?; }
+				f.write_str(name)?;
+			}
+
+			writeln!(f, "
+{HEADER_CONFIG}{:016x}
+{HEADER_CHECKSUM}{:016x}
 #
 # Eat the rich.
 #
-##########")
+##########", self.config, self.checksum)
+		}
+	}
+}
+
+
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+/// # Shitlist Metadata.
+///
+/// This holds the details parsed back out of an existing Adbyss header. Each
+/// field is optional as older versions of Adbyss recorded less information.
+pub(super) struct ShitlistMeta {
+	/// # Last Updated.
+	updated: Option<Utc2k>,
+
+	/// # Number of Entries.
+	blocked: Option<usize>,
+
+	/// # Adbyss Version.
+	version: Option<String>,
+
+	/// # Enabled Sources.
+	sources: Option<String>,
+
+	/// # Config Hash.
+	config: Option<u64>,
+
+	/// # Entry Checksum (Recorded).
+	checksum: Option<u64>,
+
+	/// # Entry Checksum (Actual).
+	actual: u64,
+}
+
+impl ShitlistMeta {
+	/// # Parse Block.
+	///
+	/// Parse the header details from a single Adbyss block (sans markers),
+	/// and checksum its entries.
+	fn parse(block: &str) -> Self {
+		let mut out = Self { actual: Checksum::body(block), ..Self::default() };
+		for line in block.lines().map(str::trim) {
+			if let Some(v) = line.strip_prefix(HEADER_UPDATED) {
+				out.updated = Utc2k::from_ascii(v.trim_end_matches(" UTC").as_bytes());
+			}
+			else if let Some(v) = line.strip_prefix(HEADER_BLOCKED) {
+				out.blocked = v.split_once(' ')
+					.and_then(|(v, _)| v.replace(',', "").parse().ok());
+			}
+			else if let Some(v) = line.strip_prefix(HEADER_VERSION) {
+				out.version = Some(v.to_owned());
+			}
+			else if let Some(v) = line.strip_prefix(HEADER_SOURCES) {
+				out.sources = Some(v.to_owned());
+			}
+			else if let Some(v) = line.strip_prefix(HEADER_CONFIG) {
+				out.config = u64::from_str_radix(v, 16).ok();
+			}
+			else if let Some(v) = line.strip_prefix(HEADER_CHECKSUM) {
+				out.checksum = u64::from_str_radix(v, 16).ok();
+			}
+		}
+		out
+	}
+
+	/// # Last Updated.
+	pub(super) const fn updated(&self) -> Option<Utc2k> { self.updated }
+
+	/// # Number of Entries.
+	pub(super) const fn blocked(&self) -> Option<usize> { self.blocked }
+
+	/// # Adbyss Version.
+	pub(super) fn version(&self) -> Option<&str> { self.version.as_deref() }
+
+	/// # Enabled Sources.
+	pub(super) fn sources(&self) -> Option<&str> { self.sources.as_deref() }
+
+	/// # Config Hash.
+	pub(super) const fn config(&self) -> Option<u64> { self.config }
+
+	/// # Hand-Edited?
+	///
+	/// Returns `true` if the entries no longer match the recorded checksum,
+	/// or `None` if there was no checksum to check.
+	pub(super) const fn edited(&self) -> Option<bool> {
+		if let Some(checksum) = self.checksum { Some(checksum != self.actual) }
+		else { None }
+	}
+}
+
+
+
+#[derive(Debug, Clone, Copy)]
+/// # Checksum.
+///
+/// This is a simple FNV-1a hasher. Unlike the standard library's hashers, its
+/// output is stable across builds, making it suitable for persisting to disk.
+pub(super) struct Checksum(u64);
+
+impl Default for Checksum {
+	#[inline]
+	fn default() -> Self { Self(0xcbf2_9ce4_8422_2325) }
+}
+
+impl fmt::Write for Checksum {
+	#[inline]
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.write(s.as_bytes());
+		Ok(())
+	}
+}
+
+impl Hasher for Checksum {
+	#[inline]
+	fn finish(&self) -> u64 { self.0 }
+
+	#[inline]
+	fn write(&mut self, bytes: &[u8]) {
+		for b in bytes {
+			self.0 ^= u64::from(*b);
+			self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
 		}
 	}
 }
 
+impl Checksum {
+	/// # Checksum Entries.
+	///
+	/// Hash the (trimmed) host lines in a block of text, ignoring comments and
+	/// empty lines, so that only meaningful changes are detected.
+	fn body(src: &str) -> u64 {
+		let mut out = Self::default();
+		for line in src.lines().map(str::trim) {
+			if ! line.is_empty() && ! line.starts_with('#') {
+				out.write(line.as_bytes());
+				out.write_u8(b'\n');
+			}
+		}
+		out.finish()
+	}
+}
+
 
 
 /// # Read Hostfile.
 ///
 /// Read the local hostfile, stripping out any Adbyss-related entries.
-pub(super) fn read_hosts<P: AsRef<Path>>(src: P) -> Result<(String, bool), AdbyssError> {
+///
+/// If an Adbyss section was present, the metadata from its header will be
+/// returned too.
+pub(super) fn read_hosts<P: AsRef<Path>>(src: P)
+-> Result<(String, Option<ShitlistMeta>), AdbyssError> {
 	// Read the file.
 	let src = src.as_ref();
 	let mut out = std::fs::read_to_string(src)
-		.map_err(|_| AdbyssError::Read(src.to_string_lossy().into_owned()))?;
+		.map_err(|e| AdbyssError::Read(src.to_string_lossy().into_owned(), e.to_string()))?;
 
 	// Strip out Adbyss parts.
-	let mut any = false;
+	let mut meta = None;
 	let len_end = MARKER_END.len();
 	let len_start = MARKER_START.len();
 
 	// Look for start markers.
 	while let Some(start) = out.find(MARKER_START) {
-		any = true;
-
 		// If we have an end marker, trim from the start of start to the end
 		// of end.
 		if let Some(end) = out[start + len_start..].find(MARKER_END) {
+			if meta.is_none() {
+				meta.replace(ShitlistMeta::parse(&out[start + len_start..start + len_start + end]));
+			}
 			out.replace_range(start..end + start + len_start + len_end, "");
 		}
 		// Otherwise we can simply truncate the file at the start of start and
 		// call it a day.
 		else {
+			if meta.is_none() {
+				meta.replace(ShitlistMeta::parse(&out[start + len_start..]));
+			}
 			out.truncate(start);
 			break;
 		}
@@ -208,7 +418,7 @@ pub(super) fn read_hosts<P: AsRef<Path>>(src: P) -> Result<(String, bool), Adbys
 	// Look for stray end markers.
 	// TODO: use remove_matches once stable.
 	while let Some(end) = out.find(MARKER_END) {
-		any = true;
+		if meta.is_none() { meta.replace(ShitlistMeta::default()); }
 		out.replace_range(end..end + len_end, "");
 	}
 
@@ -217,7 +427,54 @@ pub(super) fn read_hosts<P: AsRef<Path>>(src: P) -> Result<(String, bool), Adbys
 	out.push('\n');
 
 	// Return it!
-	Ok((out, any))
+	Ok((out, meta))
+}
+
+/// # Extract Adbyss Section.
+///
+/// Return the first Adbyss section — markers and all — from the raw hostfile
+/// content, if any.
+pub(super) fn extract_block(raw: &str) -> Option<&str> {
+	let start = raw.find(MARKER_START)?;
+	let rest = &raw[start..];
+	Some(
+		rest.find(MARKER_END)
+			.map_or(rest, |end| &rest[..end + MARKER_END.len()])
+	)
+}
+
+/// # Diff Hosts.
+///
+/// Compare the hosts blocked by the Adbyss sections of two hostfiles,
+/// returning those that were added (`true`) or removed (`false`), sorted.
+pub(super) fn diff_hosts<'a>(old: &'a str, new: &'a str) -> Vec<(bool, &'a str)> {
+	/// # Blocked Hosts.
+	fn hosts(raw: &str) -> BTreeSet<&str> {
+		extract_block(raw).map_or_else(BTreeSet::new, |block| block.lines()
+			.filter(|line| ! line.starts_with('#'))
+			.flat_map(|line| line.split_whitespace().skip(1))
+			.collect()
+		)
+	}
+
+	let old = hosts(old);
+	let new = hosts(new);
+	let mut out: Vec<(bool, &str)> = new.difference(&old).map(|h| (true, *h))
+		.chain(old.difference(&new).map(|h| (false, *h)))
+		.collect();
+	out.sort_unstable_by(|a, b| a.1.cmp(b.1));
+	out
+}
+
+/// # Same Hosts?
+///
+/// Compare two hostfiles, ignoring the timestamp in Adbyss' header, which
+/// would otherwise be different every single time.
+pub(super) fn same_hosts(a: &str, b: &str) -> bool {
+	/// # Not a Timestamp.
+	fn keep(line: &&str) -> bool { ! line.starts_with(HEADER_UPDATED) }
+
+	a.lines().filter(keep).eq(b.lines().filter(keep))
 }
 
 /// # Write to File.
@@ -231,7 +488,7 @@ pub(super) fn write_to_file(dst: &Path, data: &[u8]) -> Result<(), AdbyssError>
 		.or_else(|_| std::fs::File::create(dst).and_then(|mut file|
 			file.write_all(data).and_then(|()| file.flush())
 		))
-		.map_err(|_| AdbyssError::Write(dst.to_string_lossy().into_owned()))
+		.map_err(|e| AdbyssError::Write(dst.to_string_lossy().into_owned(), e.to_string()))
 }
 
 
@@ -304,10 +561,102 @@ mod test {
 		// Strip the adbyss chunks from the test hosts file.
 		let (stub, changed) = read_hosts("skel/test-full.hosts")
 			.expect("Failed to read hosts stub.");
-		assert!(changed, "The host should have gotten stripped.");
+		assert!(changed.is_some(), "The host should have gotten stripped.");
 		assert_eq!(stub, include_str!("../skel/test-stripped.hosts"));
 	}
 
+	#[test]
+	fn t_same_hosts() {
+		let list = Shitlist::Flat(vec![
+			Domain::new("analytics.com").unwrap(),
+			Domain::new("yahoo.com").unwrap(),
+		]);
+		let (stub, _) = read_hosts("skel/test-full.hosts")
+			.expect("Failed to read hosts stub.");
+
+		let mut a = stub.clone();
+		list.append(&mut a, Provenance::default(), 0).expect("Append failed!");
+
+		// The timestamp will change, but nothing else.
+		let b: String = a.lines()
+			.map(|l|
+				if l.starts_with(HEADER_UPDATED) { "# Updated: 1999-12-31 23:59:59 UTC" }
+				else { l }
+			)
+			.collect::<Vec<_>>()
+			.join("\n");
+		assert_ne!(a, b);
+		assert!(same_hosts(&a, &b));
+
+		// Different domains are different.
+		let mut c = stub;
+		Shitlist::Flat(vec![Domain::new("analytics.com").unwrap()])
+			.append(&mut c, Provenance::default(), 0)
+			.expect("Append failed!");
+		assert!(! same_hosts(&a, &c));
+	}
+
+	#[test]
+	fn t_meta() {
+		let list = Shitlist::Flat(vec![
+			Domain::new("analytics.com").unwrap(),
+			Domain::new("yahoo.com").unwrap(),
+		]);
+		let mut raw = String::new();
+		list.append(&mut raw, Provenance::from(crate::Source::Yoyo), 0xdead_beef)
+			.expect("Append failed!");
+
+		let meta = ShitlistMeta::parse(&raw);
+		assert!(meta.updated().is_some());
+		assert_eq!(meta.blocked(), Some(2));
+		assert_eq!(meta.version(), Some(env!("CARGO_PKG_VERSION")));
+		assert_eq!(meta.sources(), Some("Yoyo"));
+		assert_eq!(meta.config(), Some(0xdead_beef));
+		assert_eq!(meta.edited(), Some(false));
+
+		// Hand-edits should be noticed.
+		let meta = ShitlistMeta::parse(&raw.replace("yahoo.com", "yahoo.co.uk"));
+		assert_eq!(meta.edited(), Some(true));
+
+		// But not comments.
+		let meta = ShitlistMeta::parse(&raw.replace("# Eat the rich.", "# Eat the poor?"));
+		assert_eq!(meta.edited(), Some(false));
+
+		// Legacy headers have no checksum.
+		let meta = ShitlistMeta::parse("# Blocked: 1,234 garbage hosts\n0.0.0.0 yahoo.com\n");
+		assert_eq!(meta.blocked(), Some(1234));
+		assert_eq!(meta.edited(), None);
+	}
+
+	#[test]
+	fn t_extract_block() {
+		let raw = std::fs::read_to_string("skel/test-full.hosts")
+			.expect("Failed to read hosts stub.");
+		let block = extract_block(&raw).expect("Missing block.");
+		assert!(block.starts_with(MARKER_START));
+		assert!(block.ends_with(MARKER_END));
+		assert_eq!(block.matches(MARKER_START).count(), 1);
+
+		assert!(extract_block("127.0.0.1 localhost\n").is_none());
+	}
+
+	#[test]
+	fn t_diff_hosts() {
+		let old = format!("127.0.0.1 localhost\n{MARKER_START}\n0.0.0.0 a.com b.com\n0.0.0.0 d.com\n{MARKER_END}");
+		let new = format!("127.0.0.1 localhost\n{MARKER_START}\n0.0.0.0 b.com\n0.0.0.0 c.com d.com\n{MARKER_END}");
+		assert_eq!(
+			diff_hosts(&old, &new),
+			[(false, "a.com"), (true, "c.com")],
+		);
+		assert!(diff_hosts(&new, &new).is_empty());
+
+		// Everything is new if there wasn't a section before.
+		assert_eq!(
+			diff_hosts("127.0.0.1 localhost\n", &new),
+			[(true, "b.com"), (true, "c.com"), (true, "d.com")],
+		);
+	}
+
 	#[test]
 	fn t_prune_hosts() {
 		let mut list = Shitlist::Flat(vec![
@@ -344,7 +693,7 @@ mod test {
 		let (mut stub, _) = read_hosts("skel/test-full.hosts")
 			.expect("Failed to read hosts stub.");
 
-		list.append(&mut stub).expect("Append failed!");
+		list.append(&mut stub, Provenance::default(), 0).expect("Append failed!");
 		assert!(stub.contains(MARKER_START));
 		assert!(stub.contains("0.0.0.0 analytics.com"));
 		assert!(stub.contains("0.0.0.0 blobfolio.com"));
@@ -363,7 +712,7 @@ mod test {
 		let (mut stub, _) = read_hosts("skel/test-full.hosts")
 			.expect("Failed to read hosts stub.");
 
-		list.append(&mut stub).expect("Append failed!");
+		list.append(&mut stub, Provenance::default(), 0).expect("Append failed!");
 		assert!(stub.contains(MARKER_START));
 		assert!(stub.contains("0.0.0.0 analytics.com"));
 		assert!(stub.contains("0.0.0.0 blobfolio.com www.blobfolio.com"));
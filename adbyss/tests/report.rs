@@ -0,0 +1,91 @@
+/*!
+# Adbyss: Report Output
+*/
+
+use std::{
+	fs,
+	process::Command,
+};
+
+
+
+#[test]
+/// # Report Mode STDOUT.
+///
+/// `--report json` should leave STDOUT to the report alone, even under
+/// `--systemd` and with a post-write hook that prints stuff of its own.
+fn t_report_stdout() {
+	let dir = std::env::temp_dir().join(format!("adbyss-report-{}", std::process::id()));
+	fs::create_dir_all(&dir).expect("Unable to create temporary directory.");
+
+	let hostfile = dir.join("hosts");
+	fs::write(&hostfile, "127.0.0.1 localhost\n").expect("Unable to write hostfile.");
+
+	let config = dir.join("adbyss.toml");
+	fs::write(
+		&config,
+		format!(
+			concat!(
+				"hostfile = {:?}\n",
+				"backup = false\n",
+				"source_adaway = false\n",
+				"source_stevenblack = false\n",
+				"source_yoyo = false\n",
+				"post_write = [\"echo HOOKOUT\"]\n",
+			),
+			hostfile.to_string_lossy(),
+		),
+	).expect("Unable to write config.");
+
+	let out = Command::new(env!("CARGO_BIN_EXE_adbyss"))
+		.arg("-c")
+		.arg(&config)
+		.args(["--systemd", "--report", "json"])
+		.output()
+		.expect("Unable to run adbyss.");
+
+	let _res = fs::remove_dir_all(&dir);
+
+	let stdout = String::from_utf8(out.stdout).expect("STDOUT is not UTF-8.");
+	let stderr = String::from_utf8_lossy(&out.stderr);
+	assert!(out.status.success(), "Run failed:\n{stderr}");
+
+	// STDOUT should be the report and nothing but.
+	let report: serde_json::Value = serde_json::from_str(&stdout)
+		.unwrap_or_else(|e| panic!("STDOUT is not pure JSON ({e}):\n{stdout}"));
+	assert_eq!(report["success"], serde_json::Value::Bool(true));
+	assert!(! stdout.contains("HOOKOUT"), "Hook output leaked to STDOUT.");
+
+	// The hook output should have gone to STDERR instead.
+	assert!(stderr.contains("HOOKOUT"), "Hook output went missing.");
+}
+
+#[test]
+/// # Report Early Failures.
+///
+/// Problems with the config happen before anything is built, but should
+/// still make it into the report.
+fn t_report_early_failure() {
+	let dir = std::env::temp_dir().join(format!("adbyss-report-early-{}", std::process::id()));
+	fs::create_dir_all(&dir).expect("Unable to create temporary directory.");
+
+	let config = dir.join("adbyss.toml");
+	fs::write(&config, "backupp = true\n").expect("Unable to write config.");
+
+	let out = Command::new(env!("CARGO_BIN_EXE_adbyss"))
+		.arg("-c")
+		.arg(&config)
+		.args(["--report", "json"])
+		.output()
+		.expect("Unable to run adbyss.");
+
+	let _res = fs::remove_dir_all(&dir);
+
+	assert_eq!(out.status.code(), Some(10), "Expected a parse error.");
+	let stdout = String::from_utf8(out.stdout).expect("STDOUT is not UTF-8.");
+	let report: serde_json::Value = serde_json::from_str(&stdout)
+		.unwrap_or_else(|e| panic!("STDOUT is not pure JSON ({e}):\n{stdout}"));
+	assert_eq!(report["success"], serde_json::Value::Bool(false));
+	assert_eq!(report["error"]["code"], serde_json::Value::from(10));
+	assert_eq!(report["sources"], serde_json::Value::Array(Vec::new()));
+}
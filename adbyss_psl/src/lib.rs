@@ -1142,6 +1142,12 @@ const fn slices_eq(a: &[u8], b: &[u8]) -> bool {
 
 
 #[cfg(test)]
+#[expect(
+	clippy::needless_raw_string_hashes,
+	clippy::needless_raw_strings,
+	clippy::unnecessary_unwrap,
+	reason = "The test cases are written for legibility.",
+)]
 mod tests {
 	use super::*;
 	use brunch as _;
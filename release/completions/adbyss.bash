@@ -4,17 +4,33 @@ _basher___adbyss() {
 	cur="${COMP_WORDS[COMP_CWORD]}"
 	prev="${COMP_WORDS[COMP_CWORD-1]}"
 	opts=()
+	[[ " ${COMP_LINE} " =~ " --backup " ]] || opts+=("--backup")
+	[[ " ${COMP_LINE} " =~ " --check-config " ]] || opts+=("--check-config")
+	[[ " ${COMP_LINE} " =~ " --compact " ]] || opts+=("--compact")
 	[[ " ${COMP_LINE} " =~ " --disable " ]] || opts+=("--disable")
+	[[ " ${COMP_LINE} " =~ " --force " ]] || opts+=("--force")
 	if [[ ! " ${COMP_LINE} " =~ " -h " ]] && [[ ! " ${COMP_LINE} " =~ " --help " ]]; then
 		opts+=("-h")
 		opts+=("--help")
 	fi
+	[[ " ${COMP_LINE} " =~ " --init " ]] || opts+=("--init")
+	[[ " ${COMP_LINE} " =~ " --no-backup " ]] || opts+=("--no-backup")
+	[[ " ${COMP_LINE} " =~ " --no-compact " ]] || opts+=("--no-compact")
+	[[ " ${COMP_LINE} " =~ " --no-escalate " ]] || opts+=("--no-escalate")
+	[[ " ${COMP_LINE} " =~ " --no-protect-defaults " ]] || opts+=("--no-protect-defaults")
+	[[ " ${COMP_LINE} " =~ " --no-protect-strict " ]] || opts+=("--no-protect-strict")
+	[[ " ${COMP_LINE} " =~ " --print-config " ]] || opts+=("--print-config")
+	[[ " ${COMP_LINE} " =~ " --protect-defaults " ]] || opts+=("--protect-defaults")
+	[[ " ${COMP_LINE} " =~ " --protect-strict " ]] || opts+=("--protect-strict")
 	if [[ ! " ${COMP_LINE} " =~ " -q " ]] && [[ ! " ${COMP_LINE} " =~ " --quiet " ]]; then
 		opts+=("-q")
 		opts+=("--quiet")
 	fi
+	[[ " ${COMP_LINE} " =~ " --resume " ]] || opts+=("--resume")
 	[[ " ${COMP_LINE} " =~ " --show " ]] || opts+=("--show")
+	[[ " ${COMP_LINE} " =~ " --status " ]] || opts+=("--status")
 	[[ " ${COMP_LINE} " =~ " --stdout " ]] || opts+=("--stdout")
+	[[ " ${COMP_LINE} " =~ " --update " ]] || opts+=("--update")
 	if [[ ! " ${COMP_LINE} " =~ " -V " ]] && [[ ! " ${COMP_LINE} " =~ " --version " ]]; then
 		opts+=("-V")
 		opts+=("--version")
@@ -23,10 +39,30 @@ _basher___adbyss() {
 		opts+=("-y")
 		opts+=("--yes")
 	fi
+	[[ " ${COMP_LINE} " =~ " --allow-suffix " ]] || opts+=("--allow-suffix")
+	[[ " ${COMP_LINE} " =~ " --block-suffix " ]] || opts+=("--block-suffix")
+	[[ " ${COMP_LINE} " =~ " --category " ]] || opts+=("--category")
+	[[ " ${COMP_LINE} " =~ " --check " ]] || opts+=("--check")
+	[[ " ${COMP_LINE} " =~ " --cname-source " ]] || opts+=("--cname-source")
 	if [[ ! " ${COMP_LINE} " =~ " -c " ]] && [[ ! " ${COMP_LINE} " =~ " --config " ]]; then
 		opts+=("-c")
 		opts+=("--config")
 	fi
+	[[ " ${COMP_LINE} " =~ " --exclude " ]] || opts+=("--exclude")
+	[[ " ${COMP_LINE} " =~ " --format " ]] || opts+=("--format")
+	[[ " ${COMP_LINE} " =~ " --hostfile " ]] || opts+=("--hostfile")
+	[[ " ${COMP_LINE} " =~ " --include " ]] || opts+=("--include")
+	[[ " ${COMP_LINE} " =~ " --pause " ]] || opts+=("--pause")
+	[[ " ${COMP_LINE} " =~ " --post-write " ]] || opts+=("--post-write")
+	[[ " ${COMP_LINE} " =~ " --profile " ]] || opts+=("--profile")
+	[[ " ${COMP_LINE} " =~ " --protect " ]] || opts+=("--protect")
+	[[ " ${COMP_LINE} " =~ " --regexclude " ]] || opts+=("--regexclude")
+	[[ " ${COMP_LINE} " =~ " --regexinclude " ]] || opts+=("--regexinclude")
+	[[ " ${COMP_LINE} " =~ " --rejected-file " ]] || opts+=("--rejected-file")
+	[[ " ${COMP_LINE} " =~ " --report " ]] || opts+=("--report")
+	[[ " ${COMP_LINE} " =~ " --report-path " ]] || opts+=("--report-path")
+	[[ " ${COMP_LINE} " =~ " --source " ]] || opts+=("--source")
+	[[ " ${COMP_LINE} " =~ " --www-expansion " ]] || opts+=("--www-expansion")
 	opts=" ${opts[@]} "
 	if [[ ${cur} == -* || ${COMP_CWORD} -eq 1 ]] ; then
 		COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
@@ -41,6 +77,30 @@ _basher___adbyss() {
 			fi
 			return 0
 			;;
+		--hostfile)
+			if [ -z "$( declare -f _filedir )" ]; then
+				COMPREPLY=( $( compgen -f "${cur}" ) )
+			else
+				COMPREPLY=( $( _filedir ) )
+			fi
+			return 0
+			;;
+		--rejected-file)
+			if [ -z "$( declare -f _filedir )" ]; then
+				COMPREPLY=( $( compgen -f "${cur}" ) )
+			else
+				COMPREPLY=( $( _filedir ) )
+			fi
+			return 0
+			;;
+		--report-path)
+			if [ -z "$( declare -f _filedir )" ]; then
+				COMPREPLY=( $( compgen -f "${cur}" ) )
+			else
+				COMPREPLY=( $( _filedir ) )
+			fi
+			return 0
+			;;
 		*)
 			COMPREPLY=()
 			;;